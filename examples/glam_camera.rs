@@ -0,0 +1,46 @@
+//! Culls a game world's entities to what a camera sees: positions go in as
+//! glam `DVec2`s and the camera's rectangle is an area query.
+//!
+//! cargo run --example glam_camera --features glam
+
+use glam::DVec2;
+use quadrs::QuadTree2D;
+
+struct Camera {
+    center: DVec2,
+    half_extent: DVec2,
+}
+
+impl Camera {
+    fn view(&self) -> (DVec2, DVec2) {
+        (
+            self.center - self.half_extent,
+            self.center + self.half_extent,
+        )
+    }
+}
+
+fn main() {
+    let world = (DVec2::ZERO, DVec2::splat(1000.0));
+    let mut entities: QuadTree2D<DVec2, (), &str> = QuadTree2D::new(world);
+    for (x, y, name) in [
+        (120.0, 80.0, "tree"),
+        (410.0, 300.0, "player"),
+        (450.0, 260.0, "chest"),
+        (380.0, 355.0, "goblin"),
+        (900.0, 950.0, "castle"),
+    ] {
+        entities.insert(DVec2::new(x, y), (), name).unwrap();
+    }
+
+    let camera = Camera {
+        center: DVec2::new(410.0, 300.0),
+        half_extent: DVec2::new(64.0, 48.0),
+    };
+    let mut visible = entities.query_area(camera.view());
+    visible.sort_by(|a, b| a.0.x.total_cmp(&b.0.x));
+    for (position, _, name) in visible {
+        let on_screen = *position - camera.center + camera.half_extent;
+        println!("{name} at {position} is drawn at {on_screen}");
+    }
+}
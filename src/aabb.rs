@@ -0,0 +1,114 @@
+use crate::vector::Vectorial;
+
+/// Axis-aligned box. The corners are normalized on construction, `min` lies
+/// below `max` along every axis, so users don't need to sort them again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        from = "Corners<T>",
+        bound(deserialize = "T: Vectorial + serde::Deserialize<'de>")
+    )
+)]
+pub struct Aabb<T> {
+    min: T,
+    max: T,
+}
+
+/// What an [`Aabb`] is read from, normalized into one on the way in.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct Corners<T> {
+    min: T,
+    max: T,
+}
+
+#[cfg(feature = "serde")]
+impl<T: Vectorial> From<Corners<T>> for Aabb<T> {
+    fn from(corners: Corners<T>) -> Self {
+        Aabb::new(corners.min, corners.max)
+    }
+}
+
+impl<T: Vectorial> Aabb<T> {
+    /// The box spanned by two opposite corners, in any order.
+    pub fn new(a: T, b: T) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// Lower corner.
+    pub fn min(&self) -> T {
+        self.min
+    }
+
+    /// Upper corner.
+    pub fn max(&self) -> T {
+        self.max
+    }
+
+    /// Boundary included, see [`Vectorial::within`].
+    pub fn contains_point(&self, point: &T) -> bool {
+        point.within(*self)
+    }
+
+    /// Touching boundaries included, see [`Vectorial::intersects`].
+    pub fn intersects(&self, other: &Self) -> bool {
+        T::intersects(*self, *other)
+    }
+
+    /// Smallest box enclosing both.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn center(&self) -> T {
+        self.min.lerp(&self.max, 0.5)
+    }
+
+    /// Child cell `i` of the box, see [`Vectorial::split_area`].
+    pub fn split(&self, i: usize) -> Self {
+        T::split_area(*self, i)
+    }
+}
+
+impl<T: Vectorial> From<(T, T)> for Aabb<T> {
+    fn from((a, b): (T, T)) -> Self {
+        Self::new(a, b)
+    }
+}
+
+impl<T: Vectorial> From<Aabb<T>> for (T, T) {
+    fn from(area: Aabb<T>) -> Self {
+        (area.min, area.max)
+    }
+}
+
+#[test]
+fn test_aabb() {
+    use crate::vector::DefaultVector;
+    let a = Aabb::new(DefaultVector([2.0, 0.0]), DefaultVector([0.0, 1.0]));
+    assert_eq!((*a.min(), *a.max()), ([0.0, 0.0], [2.0, 1.0]));
+    assert_eq!(
+        a,
+        (DefaultVector([0.0, 1.0]), DefaultVector([2.0, 0.0])).into()
+    );
+    assert_eq!(*a.center(), [1.0, 0.5]);
+    assert!(a.contains_point(&DefaultVector([2.0, 0.5])));
+    assert!(!a.contains_point(&DefaultVector([2.5, 0.5])));
+
+    let b = Aabb::new(DefaultVector([1.5, 0.5]), DefaultVector([3.0, 3.0]));
+    let c = Aabb::new(DefaultVector([2.5, -1.0]), DefaultVector([3.0, 0.5]));
+    assert!(a.intersects(&b) && b.intersects(&c) && !a.intersects(&c));
+    let u = a.union(&c);
+    assert_eq!((*u.min(), *u.max()), ([0.0, -1.0], [3.0, 1.0]));
+
+    let cell = a.split(0b01);
+    assert_eq!((*cell.min(), *cell.max()), ([1.0, 0.0], [2.0, 0.5]));
+}
@@ -0,0 +1,1054 @@
+/*
+Same tree as `QuadTree`, with every node kept in one `Vec` and linked by index.
+*/
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::aabb::Aabb;
+use crate::naive::{Candidate, InsertError, Limits, Ray, inseparable, overfull, ray, route};
+use crate::quadtree::{DEFAULT_BUCKET_SIZE, DEFAULT_MAX_DEPTH, DuplicatePolicy};
+use crate::scalar::Scalar;
+use crate::split::{EqualSplit, SplitStrategy};
+use crate::vector::{Vectorial, check_child_count};
+
+type NodeId = u32;
+
+#[derive(Clone, Debug)]
+enum Slot<const D: usize, T, U, V> {
+    /// Reusable, its id is on the free list.
+    Free,
+    Node {
+        area: Aabb<T>,
+        cells: [Aabb<T>; D],
+        len: usize,
+        children: [Option<NodeId>; D],
+    },
+    /// Bucket of points, never empty.
+    Leaf {
+        area: Aabb<T>,
+        points: Vec<(T, U, V)>,
+    },
+}
+
+/// Alternative to [`QuadTree`](crate::QuadTree) storing its nodes in a single
+/// arena instead of one allocation per node. Slots of removed nodes are
+/// reused by later insertions. Inserting, removing, querying and iterating
+/// take and return the same as on a `QuadTree` with the default metadata, so
+/// either can sit behind a type alias; building in bulk, merging, node
+/// metadata and rendering are left to `QuadTree`.
+#[derive(Clone, Debug)]
+pub struct ArenaTree<const D: usize, T: Vectorial, U, V, S = EqualSplit> {
+    area: Aabb<T>,
+    nodes: Vec<Slot<D, T, U, V>>,
+    free: Vec<NodeId>,
+    root: Option<NodeId>,
+    len: usize,
+    limits: Limits,
+    duplicates: DuplicatePolicy,
+    strategy: PhantomData<S>,
+}
+
+impl<const D: usize, T: Vectorial, U, V, S: SplitStrategy<D, T>> ArenaTree<D, T, U, V, S> {
+    pub fn new(bounds: impl Into<Aabb<T>>) -> Self {
+        Self::with_bucket_size(bounds, DEFAULT_BUCKET_SIZE)
+    }
+
+    /// See [`QuadTree::with_bucket_size`](crate::QuadTree::with_bucket_size).
+    pub fn with_bucket_size(bounds: impl Into<Aabb<T>>, bucket_size: usize) -> Self {
+        Self::with_limits(bounds, bucket_size, DEFAULT_MAX_DEPTH)
+    }
+
+    /// See [`QuadTree::with_limits`](crate::QuadTree::with_limits).
+    pub fn with_limits(bounds: impl Into<Aabb<T>>, bucket_size: usize, max_depth: usize) -> Self {
+        assert!(bucket_size > 0, "leaves must be able to hold a point");
+        check_child_count::<D, T>();
+        Self {
+            area: bounds.into(),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+            len: 0,
+            limits: Limits {
+                capacity: bucket_size,
+                max_depth,
+            },
+            duplicates: DuplicatePolicy::Allow,
+            strategy: PhantomData,
+        }
+    }
+
+    /// See [`QuadTree::with_duplicate_policy`](crate::QuadTree::with_duplicate_policy).
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicates = policy;
+        self
+    }
+
+    /// Makes room for at least `additional` more nodes up front.
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+    }
+
+    /// See [`QuadTree::insert`](crate::QuadTree::insert).
+    pub fn insert(
+        &mut self,
+        position: impl Into<T>,
+        metadata: U,
+        data: V,
+    ) -> Result<Option<V>, InsertError>
+    where
+        T: PartialEq,
+    {
+        let position = position.into();
+        if !position.is_finite() {
+            return Err(InsertError::NonFinite);
+        }
+        if !position.within(self.area) {
+            return Err(InsertError::OutOfBounds);
+        }
+        match self.duplicates {
+            DuplicatePolicy::Allow => {}
+            DuplicatePolicy::Reject if self.contains(&position) => {
+                return Err(InsertError::Duplicate);
+            }
+            DuplicatePolicy::Reject => {}
+            DuplicatePolicy::Replace => {
+                if let Some(point) = self.find_mut(&position) {
+                    point.1 = metadata;
+                    return Ok(Some(core::mem::replace(&mut point.2, data)));
+                }
+            }
+        }
+        self.insert_below(None, self.root, self.area, 0, (position, metadata, data));
+        self.len += 1;
+        Ok(None)
+    }
+
+    /// Walks down from `current`, which sits in slot `link` of its parent (or
+    /// is the root), and stores `point` in the leaf it belongs to.
+    fn insert_below(
+        &mut self,
+        mut link: Option<(NodeId, usize)>,
+        mut current: Option<NodeId>,
+        mut area: Aabb<T>,
+        mut depth: usize,
+        point: (T, U, V),
+    ) {
+        loop {
+            let Some(id) = current else {
+                let id = self.alloc(Slot::Leaf {
+                    area,
+                    points: vec![point],
+                });
+                self.set_link(link, Some(id));
+                return;
+            };
+            match &mut self.nodes[id as usize] {
+                Slot::Free => unreachable!("free slots are never linked"),
+                Slot::Leaf { points, .. } => {
+                    points.push(point);
+                    if overfull(points, self.limits, depth) {
+                        self.split(id, depth);
+                    }
+                    return;
+                }
+                Slot::Node {
+                    cells,
+                    len,
+                    children,
+                    ..
+                } => {
+                    let i = route(cells, &point.0).expect("the cells tile the node's area");
+                    *len += 1;
+                    area = cells[i];
+                    link = Some((id, i));
+                    current = children[i];
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    /// Turns the overflowing leaf `id` into a node and spreads its points.
+    fn split(&mut self, id: NodeId, depth: usize) {
+        let Slot::Leaf { area, points } = &self.nodes[id as usize] else {
+            return;
+        };
+        let positions: Vec<T> = points.iter().map(|(p, ..)| *p).collect();
+        let node = Slot::Node {
+            area: *area,
+            cells: S::split(area, &positions),
+            len: 0,
+            children: [None; D],
+        };
+        let Slot::Leaf { area, points } = core::mem::replace(&mut self.nodes[id as usize], node)
+        else {
+            unreachable!()
+        };
+        for point in points {
+            self.insert_below(None, Some(id), area, depth, point);
+        }
+    }
+
+    /// Removes one point stored at exactly `position`.
+    pub fn remove(&mut self, position: &T) -> Option<V>
+    where
+        T: PartialEq,
+    {
+        self.take(position).map(|(_, _, data)| data)
+    }
+
+    /// Moves the point at `old_position` to `new_position`, keeping its
+    /// payload. The point is detached and inserted again from the root.
+    /// Returns `false`, leaving the tree untouched, when there is no point at
+    /// `old_position` or `new_position` is outside of the tree. A point
+    /// already at `new_position` is handled like by
+    /// [`QuadTree::relocate`](crate::QuadTree::relocate).
+    pub fn relocate(&mut self, old_position: &T, new_position: T) -> bool
+    where
+        T: PartialEq,
+    {
+        if !new_position.within(self.area) {
+            return false;
+        }
+        if *old_position != new_position && self.contains(&new_position) {
+            match self.duplicates {
+                DuplicatePolicy::Allow => {}
+                DuplicatePolicy::Reject => return false,
+                DuplicatePolicy::Replace => {
+                    let Some((_, metadata, data)) = self.take(old_position) else {
+                        return false;
+                    };
+                    let point = self.find_mut(&new_position).expect("still stored");
+                    (point.1, point.2) = (metadata, data);
+                    return true;
+                }
+            }
+        }
+        let Some((_, metadata, data)) = self.take(old_position) else {
+            return false;
+        };
+        self.insert_below(
+            None,
+            self.root,
+            self.area,
+            0,
+            (new_position, metadata, data),
+        );
+        self.len += 1;
+        true
+    }
+
+    /// See [`DNode::retain`](crate::DNode::retain).
+    pub fn retain<F: FnMut(&T, &U, &V) -> bool>(&mut self, mut f: F) {
+        if let Some(root) = self.root {
+            let (removed, kept) = self.retain_below(root, &mut f);
+            self.len -= removed;
+            if !kept {
+                self.root = None;
+            }
+        }
+    }
+
+    /// Drops the points below node `id` that `f` rejects, returning how many
+    /// those were and whether `id` is still in use.
+    fn retain_below<F: FnMut(&T, &U, &V) -> bool>(
+        &mut self,
+        id: NodeId,
+        f: &mut F,
+    ) -> (usize, bool) {
+        let children = match &mut self.nodes[id as usize] {
+            Slot::Free => unreachable!("free slots are never linked"),
+            Slot::Leaf { points, .. } => {
+                let before = points.len();
+                points.retain(|(p, u, v)| f(p, u, v));
+                let (removed, kept) = (before - points.len(), !points.is_empty());
+                if !kept {
+                    self.release(id);
+                }
+                return (removed, kept);
+            }
+            Slot::Node { children, .. } => *children,
+        };
+        let mut removed = 0;
+        for (i, child) in children.into_iter().enumerate() {
+            let Some(child) = child else { continue };
+            let (r, kept) = self.retain_below(child, f);
+            removed += r;
+            if !kept {
+                self.set_link(Some((id, i)), None);
+            }
+        }
+        if let Slot::Node { len, .. } = &mut self.nodes[id as usize] {
+            *len -= removed;
+        }
+        if self.collapse(id) {
+            self.release(id);
+            return (removed, false);
+        }
+        (removed, true)
+    }
+
+    /// Takes one point stored at exactly `position` out of the tree.
+    fn take(&mut self, position: &T) -> Option<(T, U, V)>
+    where
+        T: PartialEq,
+    {
+        let mut path: Vec<(NodeId, usize)> = Vec::new();
+        let mut current = self.root?;
+        let point = loop {
+            match &mut self.nodes[current as usize] {
+                Slot::Free => unreachable!("free slots are never linked"),
+                Slot::Leaf { points, .. } => {
+                    let i = points.iter().position(|(p, ..)| p == position)?;
+                    let point = points.swap_remove(i);
+                    if points.is_empty() {
+                        self.release(current);
+                        self.set_link(path.last().copied(), None);
+                    }
+                    break point;
+                }
+                Slot::Node {
+                    cells, children, ..
+                } => {
+                    let i = route(cells, position)?;
+                    path.push((current, i));
+                    current = children[i]?;
+                }
+            }
+        };
+        for k in (0..path.len()).rev() {
+            let (id, _) = path[k];
+            if let Slot::Node { len, .. } = &mut self.nodes[id as usize] {
+                *len -= 1;
+            }
+            if self.collapse(id) {
+                self.release(id);
+                self.set_link(k.checked_sub(1).map(|k| path[k]), None);
+            }
+        }
+        self.len -= 1;
+        Some(point)
+    }
+
+    /// Merges node `id` into a leaf when its children are all leaves whose
+    /// points fit into one (or can't be separated), returning `true` when
+    /// there are no points left at all.
+    fn collapse(&mut self, id: NodeId) -> bool {
+        let Slot::Node { area, children, .. } = &self.nodes[id as usize] else {
+            return false;
+        };
+        let (area, children) = (*area, *children);
+        let (mut total, mut first, mut same) = (0, None, true);
+        for child in children.iter().flatten() {
+            let Slot::Leaf { points, .. } = &self.nodes[*child as usize] else {
+                return false;
+            };
+            let f = *first.get_or_insert(points[0].0);
+            total += points.len();
+            same &= inseparable(points) && points[0].0.within(Aabb::new(f, f));
+        }
+        if total == 0 {
+            return true;
+        }
+        if total > self.limits.capacity && !same {
+            return false;
+        }
+        let mut points = Vec::with_capacity(total);
+        for child in children.into_iter().flatten() {
+            if let Slot::Leaf { points: p, .. } = self.release(child) {
+                points.extend(p);
+            }
+        }
+        self.nodes[id as usize] = Slot::Leaf { area, points };
+        false
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V, S> ArenaTree<D, T, U, V, S> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn bounds(&self) -> Aabb<T> {
+        self.area
+    }
+
+    pub fn bucket_size(&self) -> usize {
+        self.limits.capacity
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.limits.max_depth
+    }
+
+    /// Drops every stored point, keeping the bounds, the settings and the
+    /// allocated arena for reuse.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// See [`QuadTree::depth`](crate::QuadTree::depth).
+    pub fn depth(&self) -> usize {
+        let mut deepest: usize = 0;
+        let mut stack: Vec<_> = self.root.map(|id| (1, id)).into_iter().collect();
+        while let Some((level, id)) = stack.pop() {
+            deepest = deepest.max(level);
+            if let Slot::Node { children, .. } = &self.nodes[id as usize] {
+                stack.extend(children.iter().flatten().map(|&child| (level + 1, child)));
+            }
+        }
+        deepest
+    }
+
+    /// Number of nodes the arena can hold without reallocating.
+    pub fn node_capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// Whether a point is stored at exactly `position`.
+    pub fn contains(&self, position: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.leaf_of(position)
+            .is_some_and(|id| match &self.nodes[id as usize] {
+                Slot::Leaf { points, .. } => points.iter().any(|(p, ..)| p == position),
+                _ => false,
+            })
+    }
+
+    /// A point stored at exactly `position`.
+    fn find_mut(&mut self, position: &T) -> Option<&mut (T, U, V)>
+    where
+        T: PartialEq,
+    {
+        let id = self.leaf_of(position)?;
+        match &mut self.nodes[id as usize] {
+            Slot::Leaf { points, .. } => points.iter_mut().find(|(p, ..)| p == position),
+            _ => None,
+        }
+    }
+
+    /// The leaf `position` would be stored in, `None` when that cell is empty.
+    fn leaf_of(&self, position: &T) -> Option<NodeId> {
+        let mut id = self.root?;
+        loop {
+            match &self.nodes[id as usize] {
+                Slot::Free => unreachable!("free slots are never linked"),
+                Slot::Leaf { .. } => return Some(id),
+                Slot::Node {
+                    cells, children, ..
+                } => id = children[route(cells, position)?]?,
+            }
+        }
+    }
+
+    /// Every stored point within the axis-aligned box `area`, boundaries included.
+    pub fn query_area(&self, area: impl Into<Aabb<T>>) -> Vec<(&T, &U, &V)> {
+        let area = area.into();
+        let mut found = Vec::new();
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            match &self.nodes[id as usize] {
+                Slot::Free => unreachable!("free slots are never linked"),
+                Slot::Leaf { points, .. } => {
+                    for (position, metadata, data) in points {
+                        if position.within(area) {
+                            found.push((position, metadata, data));
+                        }
+                    }
+                }
+                Slot::Node {
+                    area: node_area,
+                    children,
+                    ..
+                } => {
+                    if T::intersects(*node_area, area) {
+                        stack.extend(children.iter().rev().flatten());
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Calls `f` on every stored point within `area` to change its metadata
+    /// and data in place.
+    pub fn for_each_in_range<F: FnMut(&T, &mut U, &mut V)>(
+        &mut self,
+        area: impl Into<Aabb<T>>,
+        mut f: F,
+    ) {
+        let area = area.into();
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            match &mut self.nodes[id as usize] {
+                Slot::Free => unreachable!("free slots are never linked"),
+                Slot::Leaf { points, .. } => {
+                    for (position, metadata, data) in points {
+                        if position.within(area) {
+                            f(position, metadata, data);
+                        }
+                    }
+                }
+                Slot::Node {
+                    area: node_area,
+                    children,
+                    ..
+                } => {
+                    if T::intersects(*node_area, area) {
+                        stack.extend(children.iter().rev().flatten());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Number of stored points within `area`. Nodes covered by `area` as a
+    /// whole contribute their count without being descended into.
+    pub fn count_in_area(&self, area: impl Into<Aabb<T>>) -> usize {
+        let area = area.into();
+        let mut count = 0;
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            match &self.nodes[id as usize] {
+                Slot::Free => unreachable!("free slots are never linked"),
+                Slot::Leaf { points, .. } => {
+                    count += points.iter().filter(|(p, ..)| p.within(area)).count();
+                }
+                Slot::Node {
+                    area: node_area,
+                    len,
+                    children,
+                    ..
+                } => {
+                    if node_area.min().within(area) && node_area.max().within(area) {
+                        count += len;
+                    } else if T::intersects(*node_area, area) {
+                        stack.extend(children.iter().flatten());
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    /// The stored point closest to `target` along with its data.
+    pub fn nearest(&self, target: T) -> Option<(&T, &V)> {
+        let (mut best, mut best_dist) = (None, f64::INFINITY);
+        if let Some(root) = self.root {
+            self.nearest_in(root, &target, &mut best, &mut best_dist);
+        }
+        best
+    }
+
+    fn nearest_in<'a>(
+        &'a self,
+        id: NodeId,
+        point: &T,
+        best: &mut Option<(&'a T, &'a V)>,
+        best_dist: &mut f64,
+    ) {
+        match &self.nodes[id as usize] {
+            Slot::Free => unreachable!("free slots are never linked"),
+            Slot::Leaf { points, .. } => {
+                for (position, _, data) in points {
+                    let dist = position.distance_squared(point);
+                    if dist < *best_dist {
+                        *best_dist = dist;
+                        *best = Some((position, data));
+                    }
+                }
+            }
+            Slot::Node {
+                cells, children, ..
+            } => {
+                for (dist, i) in by_distance(cells, children, point) {
+                    let Some(child) = children[i].filter(|_| dist < *best_dist) else {
+                        break;
+                    };
+                    self.nearest_in(child, point, best, best_dist);
+                }
+            }
+        }
+    }
+
+    /// Up to `k` stored points sorted by increasing distance to `target`.
+    pub fn knn(&self, target: T, k: usize) -> Vec<(&T, &V)> {
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        if let (Some(root), true) = (self.root, k > 0) {
+            self.knn_in(root, &target, k, &mut heap);
+        }
+        heap.into_sorted_vec().into_iter().map(|c| c.item).collect()
+    }
+
+    fn knn_in<'a>(
+        &'a self,
+        id: NodeId,
+        point: &T,
+        k: usize,
+        heap: &mut BinaryHeap<Candidate<(&'a T, &'a V)>>,
+    ) {
+        // Worst distance still worth looking at once the heap is full.
+        let bound = |heap: &BinaryHeap<Candidate<_>>| match heap.peek() {
+            Some(worst) if heap.len() == k => worst.dist,
+            _ => f64::INFINITY,
+        };
+        match &self.nodes[id as usize] {
+            Slot::Free => unreachable!("free slots are never linked"),
+            Slot::Leaf { points, .. } => {
+                for (position, _, data) in points {
+                    let dist = position.distance_squared(point);
+                    if dist < bound(heap) {
+                        if heap.len() == k {
+                            heap.pop();
+                        }
+                        heap.push(Candidate {
+                            dist,
+                            item: (position, data),
+                        });
+                    }
+                }
+            }
+            Slot::Node {
+                cells, children, ..
+            } => {
+                for (dist, i) in by_distance(cells, children, point) {
+                    let Some(child) = children[i].filter(|_| dist < bound(heap)) else {
+                        break;
+                    };
+                    self.knn_in(child, point, k, heap);
+                }
+            }
+        }
+    }
+
+    /// Every stored point at most `radius` away from `center`, boundary
+    /// included, none when `radius` is negative or `NaN`.
+    pub fn within_radius(&self, center: T, radius: f64) -> Vec<(&T, &V)> {
+        let mut found = Vec::new();
+        self.for_each_within_radius(center, radius, |position, data| {
+            found.push((position, data))
+        });
+        found
+    }
+
+    /// Like [`ArenaTree::within_radius`] without collecting the matches.
+    pub fn for_each_within_radius<'a, F>(&'a self, center: T, radius: f64, mut f: F)
+    where
+        F: FnMut(&'a T, &'a V),
+    {
+        if radius.is_nan() || radius < 0.0 {
+            return;
+        }
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            match &self.nodes[id as usize] {
+                Slot::Free => unreachable!("free slots are never linked"),
+                Slot::Leaf { points, .. } => {
+                    for (position, _, data) in points {
+                        if position.distance_squared(&center) <= radius * radius {
+                            f(position, data);
+                        }
+                    }
+                }
+                Slot::Node { area, children, .. } => {
+                    if center.min_distance_squared_to_area(*area) <= radius * radius {
+                        stack.extend(children.iter().rev().flatten());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Like [`ArenaTree::within_radius`], with the metadata of each point too.
+    /// Queries the box around the circle, see
+    /// [`DNode::query_radius`](crate::DNode::query_radius).
+    pub fn query_radius(&self, center: T, radius: f64) -> Vec<(&T, &U, &V)> {
+        if radius.is_nan() || radius < 0.0 {
+            return Vec::new();
+        }
+        let (mut lo, mut hi) = (center, center);
+        for k in 0..D.trailing_zeros() as usize {
+            let c = center.component(k).to_f64();
+            lo = lo.with_component(k, T::Scalar::from_f64(c - radius));
+            hi = hi.with_component(k, T::Scalar::from_f64(c + radius));
+        }
+        let mut found = self.query_area(Aabb::new(lo, hi));
+        found.retain(|(position, ..)| position.distance_squared(&center) <= radius * radius);
+        found
+    }
+
+    /// See [`QuadTree::query_ray`](crate::QuadTree::query_ray).
+    pub fn query_ray(
+        &self,
+        origin: T,
+        direction: T,
+        max_t: f64,
+        radius: f64,
+    ) -> Vec<(&T, &V, f64)> {
+        if ray::rejects(max_t, radius) {
+            return Vec::new();
+        }
+        let ray = Self::ray(&origin, &direction, max_t);
+        let mut found = Vec::new();
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            match &self.nodes[id as usize] {
+                Slot::Free => unreachable!("free slots are never linked"),
+                Slot::Leaf { points, .. } => {
+                    for (position, _, data) in points {
+                        let (t, dist) = ray.closest(position);
+                        if dist <= radius * radius {
+                            found.push((position, data, t));
+                        }
+                    }
+                }
+                Slot::Node { area, children, .. } => {
+                    if ray.entry(*area, radius).is_some() {
+                        stack.extend(children.iter().flatten());
+                    }
+                }
+            }
+        }
+        found.sort_by(|a, b| a.2.total_cmp(&b.2));
+        found
+    }
+
+    /// See [`QuadTree::query_ray_first`](crate::QuadTree::query_ray_first).
+    pub fn query_ray_first(
+        &self,
+        origin: T,
+        direction: T,
+        max_t: f64,
+        radius: f64,
+    ) -> Option<(&T, &V, f64)> {
+        if ray::rejects(max_t, radius) {
+            return None;
+        }
+        let ray = Self::ray(&origin, &direction, max_t);
+        let mut best = None;
+        if let Some(root) = self.root {
+            self.ray_first_in(root, &ray, radius, &mut best);
+        }
+        best
+    }
+
+    fn ray<'a>(origin: &'a T, direction: &'a T, max_t: f64) -> Ray<'a, T> {
+        Ray {
+            origin,
+            direction,
+            max_t,
+            axes: D.trailing_zeros() as usize,
+        }
+    }
+
+    fn ray_first_in<'a>(
+        &'a self,
+        id: NodeId,
+        ray: &Ray<'_, T>,
+        radius: f64,
+        best: &mut Option<(&'a T, &'a V, f64)>,
+    ) {
+        let best_t = |best: &Option<(_, _, f64)>| best.map_or(f64::INFINITY, |b| b.2);
+        match &self.nodes[id as usize] {
+            Slot::Free => unreachable!("free slots are never linked"),
+            Slot::Leaf { points, .. } => {
+                for (position, _, data) in points {
+                    let (t, dist) = ray.closest(position);
+                    if dist <= radius * radius && t < best_t(best) {
+                        *best = Some((position, data, t));
+                    }
+                }
+            }
+            Slot::Node {
+                cells, children, ..
+            } => {
+                let mut order: [_; D] = core::array::from_fn(|i| {
+                    let entry = children[i].and_then(|_| ray.entry(cells[i], radius));
+                    (entry.unwrap_or(f64::INFINITY), i)
+                });
+                order.sort_by(|a, b| a.0.total_cmp(&b.0));
+                for (entry, i) in order {
+                    if entry == f64::INFINITY || entry > best_t(best) {
+                        break;
+                    }
+                    if let Some(child) = children[i] {
+                        self.ray_first_in(child, ray, radius, best);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Iterates over every stored point, children visited in index order.
+    pub fn iter(&self) -> ArenaIter<'_, D, T, U, V> {
+        ArenaIter {
+            nodes: &self.nodes,
+            stack: self.root.into_iter().collect(),
+            points: Default::default(),
+        }
+    }
+
+    /// Iterates over every stored point with mutable access to its payload,
+    /// in no particular order. Use [`ArenaTree::relocate`] to move points.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&T, &mut U, &mut V)> {
+        self.nodes
+            .iter_mut()
+            .flat_map(|slot| match slot {
+                Slot::Leaf { points, .. } => points.as_mut_slice(),
+                _ => &mut [],
+            })
+            .map(|(position, metadata, data)| (&*position, metadata, data))
+    }
+
+    /// Moves every stored point out, in no particular order. The tree keeps
+    /// its area, its settings and the allocated arena, and can be filled
+    /// again right away.
+    pub fn drain(&mut self) -> impl Iterator<Item = (T, U, V)> + '_ {
+        self.free.clear();
+        self.root = None;
+        self.len = 0;
+        self.nodes.drain(..).flat_map(|slot| match slot {
+            Slot::Leaf { points, .. } => points,
+            _ => Vec::new(),
+        })
+    }
+
+    fn alloc(&mut self, slot: Slot<D, T, U, V>) -> NodeId {
+        if let Some(id) = self.free.pop() {
+            self.nodes[id as usize] = slot;
+            return id;
+        }
+        let id = NodeId::try_from(self.nodes.len()).expect("arena is full");
+        if self.nodes.len() == self.nodes.capacity() {
+            // Growing fourfold instead of twofold keeps the arena to a
+            // single-digit number of allocations for a million points.
+            self.nodes.reserve(self.nodes.len().max(16) * 3);
+        }
+        self.nodes.push(slot);
+        id
+    }
+
+    /// Frees slot `id`, handing back what it held.
+    fn release(&mut self, id: NodeId) -> Slot<D, T, U, V> {
+        self.free.push(id);
+        core::mem::replace(&mut self.nodes[id as usize], Slot::Free)
+    }
+
+    fn set_link(&mut self, link: Option<(NodeId, usize)>, child: Option<NodeId>) {
+        match link {
+            None => self.root = child,
+            Some((parent, i)) => {
+                if let Slot::Node { children, .. } = &mut self.nodes[parent as usize] {
+                    children[i] = child;
+                }
+            }
+        }
+    }
+}
+
+/// Child slots ordered by the distance of their cell to `point`, empty ones
+/// sorting last.
+fn by_distance<const D: usize, T: Vectorial>(
+    cells: &[Aabb<T>; D],
+    children: &[Option<NodeId>; D],
+    point: &T,
+) -> [(f64, usize); D] {
+    let mut order = core::array::from_fn(|i| {
+        let dist = children[i].map_or(f64::INFINITY, |_| {
+            point.min_distance_squared_to_area(cells[i])
+        });
+        (dist, i)
+    });
+    order.sort_by(|a, b| a.0.total_cmp(&b.0));
+    order
+}
+
+/// Depth-first iterator over the points of an [`ArenaTree`].
+pub struct ArenaIter<'a, const D: usize, T, U, V> {
+    nodes: &'a [Slot<D, T, U, V>],
+    stack: Vec<NodeId>,
+    points: core::slice::Iter<'a, (T, U, V)>,
+}
+
+impl<'a, const D: usize, T, U, V> Iterator for ArenaIter<'a, D, T, U, V> {
+    type Item = (&'a T, &'a U, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((position, metadata, data)) = self.points.next() {
+                return Some((position, metadata, data));
+            }
+            match &self.nodes[self.stack.pop()? as usize] {
+                Slot::Free => unreachable!("free slots are never linked"),
+                Slot::Leaf { points, .. } => self.points = points.iter(),
+                Slot::Node { children, .. } => self.stack.extend(children.iter().rev().flatten()),
+            }
+        }
+    }
+}
+
+impl<const D: usize, T, U, V> core::iter::FusedIterator for ArenaIter<'_, D, T, U, V> {}
+
+impl<'a, const D: usize, T: Vectorial, U, V, S> IntoIterator for &'a ArenaTree<D, T, U, V, S> {
+    type Item = (&'a T, &'a U, &'a V);
+    type IntoIter = ArenaIter<'a, D, T, U, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[test]
+fn test_arena_matches_quadtree() {
+    use crate::naive::sorted;
+    use crate::vector::DefaultVector;
+    let (tree, points) = crate::naive::random_tree(500, 67, 2);
+    let mut arena = ArenaTree::<4, _, (), usize>::with_bucket_size(crate::naive::UNIT_SQUARE, 2);
+    for (i, p) in points.iter().enumerate() {
+        arena.insert(*p, (), i).unwrap();
+    }
+    assert_eq!(
+        arena.insert(DefaultVector([1.5, 0.0]), (), 0),
+        Err(InsertError::OutOfBounds)
+    );
+    let query = Aabb::new(DefaultVector([0.1, 0.3]), DefaultVector([0.6, 0.5]));
+    assert_eq!(
+        sorted(arena.query_area(query).iter().map(|(.., i)| **i).collect()),
+        sorted(tree.query_area(query).iter().map(|(.., i)| **i).collect())
+    );
+    assert_eq!(
+        sorted(arena.iter().map(|(.., i)| *i).collect()),
+        (0..500).collect::<Vec<_>>()
+    );
+
+    for p in &points[..400] {
+        assert!(arena.remove(p).is_some());
+        assert!(!arena.contains(p));
+    }
+    assert!(points[400..].iter().all(|p| arena.contains(p)));
+    assert_eq!(arena.len(), 100);
+    assert_eq!(arena.iter().count(), 100);
+
+    // Freed slots get reused instead of growing the arena.
+    let slots = arena.nodes.len();
+    for (i, p) in points[..400].iter().enumerate() {
+        arena.insert(*p, (), i).unwrap();
+    }
+    assert!(arena.nodes.len() <= slots + 5);
+    for p in &points {
+        arena.remove(p).unwrap();
+    }
+    assert!(arena.is_empty());
+    assert_eq!(arena.root, None);
+    assert_eq!(arena.free.len(), arena.nodes.len());
+}
+
+#[test]
+fn test_arena_swaps_for_quadtree() {
+    use crate::QuadTree;
+    use crate::naive::sorted;
+    use crate::vector::DefaultVector;
+    let points = crate::naive::random_points(400, 29);
+    let moved = points.iter().position(|p| p[0] < 0.8).unwrap();
+    // The same code run against either type, as behind a type alias.
+    macro_rules! exercise {
+        ($tree:ty) => {{
+            let mut tree = <$tree>::with_bucket_size(([0.0, 0.0], [1.0, 1.0]), 3)
+                .with_duplicate_policy(DuplicatePolicy::Replace);
+            for (i, p) in points.iter().enumerate() {
+                assert_eq!(tree.insert(p.0, (), i), Ok(None));
+            }
+            assert_eq!(tree.insert(points[7], (), 1000), Ok(Some(7)));
+            let target = DefaultVector([0.4, 0.6]);
+            let queries = (
+                tree.nearest(target).map(|(_, i)| *i),
+                tree.knn(target, 12)
+                    .iter()
+                    .map(|(_, i)| **i)
+                    .collect::<Vec<_>>(),
+                sorted(
+                    tree.within_radius(target, 0.2)
+                        .iter()
+                        .map(|(_, i)| **i)
+                        .collect(),
+                ),
+                sorted(
+                    tree.query_radius(target, 0.2)
+                        .iter()
+                        .map(|h| *h.2)
+                        .collect(),
+                ),
+                tree.count_in_area(([0.1, 0.2], [0.7, 0.5])),
+                tree.query_ray(
+                    DefaultVector([0.0, 0.1]),
+                    DefaultVector([1.0, 0.8]),
+                    1.2,
+                    0.03,
+                )
+                .iter()
+                .map(|(.., t)| *t)
+                .collect::<Vec<_>>(),
+                tree.query_ray_first(
+                    DefaultVector([1.0, 1.0]),
+                    DefaultVector([-1.0, -0.5]),
+                    1.0,
+                    0.02,
+                )
+                .map(|(.., t)| t),
+            );
+            tree.for_each_in_range(([0.0, 0.0], [0.5, 0.5]), |_, _, i| *i += 10_000);
+            for (_, _, i) in tree.iter_mut() {
+                *i += 1;
+            }
+            tree.retain(|p, _, _| p[0] < 0.8);
+            assert!(tree.relocate(&points[moved], DefaultVector([0.95, 0.95])));
+            assert!(!tree.relocate(&points[moved], DefaultVector([0.5, 0.5])));
+            let relocated = tree.remove(&DefaultVector([0.95, 0.95]));
+            // Onto a taken position, replacing the point there, then refused.
+            let taken: Vec<_> = points.iter().filter(|p| tree.contains(p)).take(3).collect();
+            assert!(tree.relocate(taken[0], *taken[1]));
+            assert!(!tree.contains(taken[0]));
+            let mut tree = tree.with_duplicate_policy(DuplicatePolicy::Reject);
+            assert!(!tree.relocate(taken[1], *taken[2]));
+            assert!(tree.contains(taken[1]));
+            let len = tree.len();
+            let drained = sorted(tree.drain().map(|(.., i)| i).collect());
+            assert_eq!((drained.len(), tree.len()), (len, 0));
+            (queries, relocated, drained)
+        }};
+    }
+    let arena = exercise!(ArenaTree<4, DefaultVector<2>, (), usize>);
+    let tree = exercise!(QuadTree<4, DefaultVector<2>, (), usize>);
+    assert_eq!(arena, tree);
+    assert!(arena.1.is_some());
+    assert!(!arena.2.is_empty() && arena.2.iter().any(|i| *i > 10_000));
+}
+
+#[test]
+fn test_arena_allocations() {
+    // Without reserving anything up front.
+    let n = 1_000_000;
+    let mut arena = ArenaTree::<4, _, (), ()>::new(crate::naive::UNIT_SQUARE);
+    let mut capacity = arena.node_capacity();
+    let mut allocations = 0;
+    for p in crate::naive::random_points(n, 71) {
+        arena.insert(p, (), ()).unwrap();
+        if arena.node_capacity() != capacity {
+            capacity = arena.node_capacity();
+            allocations += 1;
+        }
+    }
+    assert_eq!(arena.len(), n);
+    assert!(allocations < 10, "{allocations} node allocations");
+}
@@ -0,0 +1,318 @@
+use std::marker::PhantomData;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+#[cfg(feature = "rayon")]
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use crate::aabb::Aabb;
+use crate::metadata::{DefaultMetadata, NodeMetadata};
+use crate::naive::{DNode, InsertError, Limits, route};
+use crate::quadtree::{DEFAULT_BUCKET_SIZE, DEFAULT_MAX_DEPTH};
+use crate::split::{EqualSplit, SplitStrategy};
+use crate::vector::{Vectorial, check_child_count};
+
+type Cell<const D: usize, T, U, V> = Arc<RwLock<DNode<D, T, U, V>>>;
+
+/// Tree shared between threads. The bounds are split into `D` cells up front,
+/// each guarding its subtree with its own lock: writers to different cells
+/// don't wait on each other, and readers only wait on writers to the cells
+/// they look at. Locking stops at these top level cells, nodes further down
+/// have no locks of their own, so writers to the same cell take turns however
+/// far apart their points are. The top level cells always bisect the bounds,
+/// `S` applies to splits within them. Clones share the same points.
+pub struct ConcurrentQuadTree<
+    const D: usize,
+    T: Vectorial,
+    U,
+    V,
+    S = EqualSplit,
+    M = DefaultMetadata,
+> {
+    area: Aabb<T>,
+    cells: [Aabb<T>; D],
+    children: [Cell<D, T, U, V>; D],
+    limits: Limits,
+    strategy: PhantomData<(S, M)>,
+}
+
+impl<const D: usize, T: Vectorial, U, V, S: SplitStrategy<D, T>, M: NodeMetadata<U>>
+    ConcurrentQuadTree<D, T, U, V, S, M>
+{
+    pub fn new(bounds: impl Into<Aabb<T>>) -> Self {
+        Self::with_bucket_size(bounds, DEFAULT_BUCKET_SIZE)
+    }
+
+    /// See [`QuadTree::with_bucket_size`](crate::QuadTree::with_bucket_size).
+    pub fn with_bucket_size(bounds: impl Into<Aabb<T>>, bucket_size: usize) -> Self {
+        Self::with_limits(bounds, bucket_size, DEFAULT_MAX_DEPTH)
+    }
+
+    /// See [`QuadTree::with_limits`](crate::QuadTree::with_limits), the
+    /// top level cells count as the first level below the root.
+    pub fn with_limits(bounds: impl Into<Aabb<T>>, bucket_size: usize, max_depth: usize) -> Self {
+        assert!(bucket_size > 0, "leaves must be able to hold a point");
+        assert!(max_depth > 0, "the bounds are always split once");
+        check_child_count::<D, T>();
+        let bounds = bounds.into();
+        Self {
+            area: bounds,
+            cells: std::array::from_fn(|i| bounds.split(i)),
+            children: std::array::from_fn(|_| Arc::new(RwLock::new(DNode::None))),
+            limits: Limits {
+                capacity: bucket_size,
+                max_depth,
+            },
+            strategy: PhantomData,
+        }
+    }
+
+    /// Blocks while another thread writes to or reads from the cell
+    /// `position` lies in.
+    pub fn insert(&self, position: T, metadata: U, data: V) -> Result<(), InsertError> {
+        if !position.is_finite() {
+            return Err(InsertError::NonFinite);
+        }
+        let i = route(&self.cells, &position).ok_or(InsertError::OutOfBounds)?;
+        write(&self.children[i]).insert_point::<S, M>(
+            self.cells[i],
+            (position, metadata, data),
+            self.limits,
+            1,
+        )
+    }
+
+    /// Removes one point stored at exactly `position`.
+    pub fn remove(&self, position: &T) -> Option<V>
+    where
+        T: PartialEq,
+    {
+        let i = route(&self.cells, position)?;
+        let (_, _, data) = write(&self.children[i]).remove_point(position, self.limits.capacity)?;
+        Some(data)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<const D: usize, T, U, V, S, M> ConcurrentQuadTree<D, T, U, V, S, M>
+where
+    T: Vectorial + Send + Sync,
+    U: Send + Sync,
+    V: Send + Sync,
+    S: SplitStrategy<D, T>,
+    M: NodeMetadata<U>,
+{
+    /// Inserts `points` from rayon's thread pool, handing back the ones
+    /// [`ConcurrentQuadTree::insert`] would reject. They are sorted into the
+    /// top level cells first, then the cells are filled in parallel, each
+    /// taking its write lock once rather than per point.
+    pub fn par_extend(
+        &self,
+        points: impl IntoParallelIterator<Item = (T, U, V)>,
+    ) -> Vec<(T, U, V)> {
+        let (cells, children, limits) = (&self.cells, &self.children, self.limits);
+        let empty = || {
+            (
+                std::array::from_fn::<Vec<_>, D, _>(|_| Vec::new()),
+                Vec::new(),
+            )
+        };
+        let (shares, rejects) = points
+            .into_par_iter()
+            .fold(empty, |(mut shares, mut rejects), point| {
+                match route(cells, &point.0).filter(|_| point.0.is_finite()) {
+                    Some(i) => shares[i].push(point),
+                    None => rejects.push(point),
+                }
+                (shares, rejects)
+            })
+            .reduce(empty, |(mut shares, mut rejects), (more, rejected)| {
+                for (share, more) in shares.iter_mut().zip(more) {
+                    share.extend(more);
+                }
+                rejects.extend(rejected);
+                (shares, rejects)
+            });
+        shares.into_par_iter().enumerate().for_each(|(i, share)| {
+            let mut child = write(&children[i]);
+            for point in share {
+                child
+                    .insert_point::<S, M>(cells[i], point, limits, 1)
+                    .expect("points are routed into their cell");
+            }
+        });
+        rejects
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V, S, M> ConcurrentQuadTree<D, T, U, V, S, M> {
+    pub fn bucket_size(&self) -> usize {
+        self.limits.capacity
+    }
+
+    /// Number of stored points. Other threads may change it right away.
+    pub fn len(&self) -> usize {
+        self.children.iter().map(|c| read(c).len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn bounds(&self) -> Aabb<T> {
+        self.area
+    }
+
+    /// Drops every stored point, keeping the bounds and settings. Cells are
+    /// cleared one after another, so other threads may see some of them
+    /// cleared already and insert in between.
+    pub fn clear(&self) {
+        for child in &self.children {
+            write(child).clear();
+        }
+    }
+
+    pub fn contains(&self, position: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        route(&self.cells, position).is_some_and(|i| read(&self.children[i]).contains(position))
+    }
+
+    /// See [`DNode::count_in_area`].
+    pub fn count_in_area(&self, area: impl Into<Aabb<T>>) -> usize {
+        let area = area.into();
+        self.overlapping(area)
+            .map(|i| read(&self.children[i]).count_in_area(area))
+            .sum()
+    }
+
+    /// Calls `f` on every stored point within `area`. Each cell stays locked
+    /// for reading while `f` is called on its points, so `f` must not write
+    /// to the tree.
+    pub fn for_each_in_area<F: FnMut(&T, &U, &V)>(&self, area: impl Into<Aabb<T>>, mut f: F) {
+        let area = area.into();
+        for i in self.overlapping(area) {
+            for (p, u, v) in read(&self.children[i]).query_range(area) {
+                f(p, u, v);
+            }
+        }
+    }
+
+    /// Indices of the top level cells overlapping `area`.
+    fn overlapping(&self, area: Aabb<T>) -> impl Iterator<Item = usize> + '_ {
+        (0..D).filter(move |&i| T::intersects(self.cells[i], area))
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V, S, M> Clone for ConcurrentQuadTree<D, T, U, V, S, M> {
+    /// Another handle to the same tree.
+    fn clone(&self) -> Self {
+        Self {
+            area: self.area,
+            cells: self.cells,
+            children: self.children.clone(),
+            limits: self.limits,
+            strategy: PhantomData,
+        }
+    }
+}
+
+fn read<const D: usize, T: Vectorial, U, V>(
+    cell: &Cell<D, T, U, V>,
+) -> RwLockReadGuard<'_, DNode<D, T, U, V>> {
+    cell.read()
+        .expect("a thread panicked while writing to the tree")
+}
+
+fn write<const D: usize, T: Vectorial, U, V>(
+    cell: &Cell<D, T, U, V>,
+) -> RwLockWriteGuard<'_, DNode<D, T, U, V>> {
+    cell.write()
+        .expect("a thread panicked while writing to the tree")
+}
+
+#[test]
+fn test_concurrent_insert() {
+    use crate::QuadTree;
+    use crate::naive::random_points;
+    use crate::vector::DefaultVector;
+    let bounds = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let tree = ConcurrentQuadTree::<4, _, (), usize>::with_bucket_size(bounds, 4);
+    let points = random_points(4000, 53);
+    std::thread::scope(|s| {
+        for (t, chunk) in points.chunks(1000).enumerate() {
+            let tree = tree.clone();
+            s.spawn(move || {
+                for (i, p) in chunk.iter().enumerate() {
+                    tree.insert(*p, (), t * 1000 + i).unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(tree.len(), points.len());
+    assert_eq!(
+        tree.insert(DefaultVector([1.5, 0.0]), (), 0),
+        Err(InsertError::OutOfBounds)
+    );
+
+    let mut sequential = QuadTree::<4, _, (), usize>::with_bucket_size(bounds, 4);
+    for (i, p) in points.iter().enumerate() {
+        sequential.insert(*p, (), i).unwrap();
+    }
+    let query = Aabb::new(DefaultVector([0.3, 0.2]), DefaultVector([0.7, 0.55]));
+    let mut found = Vec::new();
+    tree.for_each_in_area(query, |_, _, i| found.push(*i));
+    let mut expected: Vec<_> = sequential
+        .query_area(query)
+        .iter()
+        .map(|(.., i)| **i)
+        .collect();
+    found.sort();
+    expected.sort();
+    assert_eq!(found, expected);
+    assert_eq!(tree.count_in_area(query), expected.len());
+
+    assert!(tree.contains(&points[17]));
+    assert_eq!(tree.remove(&points[17]), Some(17));
+    assert!(!tree.contains(&points[17]));
+    assert_eq!(tree.len(), points.len() - 1);
+    tree.clear();
+    assert!(tree.is_empty());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par_extend() {
+    use crate::naive::sorted;
+    use crate::vector::DefaultVector;
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+    let (sequential, points) = crate::naive::random_tree(4000, 59, 4);
+    let tree =
+        ConcurrentQuadTree::<4, _, (), usize>::with_bucket_size(crate::naive::UNIT_SQUARE, 4);
+    let outside = [DefaultVector([1.5, 0.5]), DefaultVector([f64::NAN, 0.5])];
+    let rejects = tree.par_extend(
+        points
+            .clone()
+            .into_par_iter()
+            .chain(outside)
+            .enumerate()
+            .map(|(i, p)| (p, (), i)),
+    );
+    assert_eq!(
+        sorted(rejects.iter().map(|(.., i)| *i).collect()),
+        [4000, 4001]
+    );
+    assert_eq!(tree.len(), points.len());
+    for child in &tree.children {
+        read(child).assert_valid();
+    }
+    let query = Aabb::new(DefaultVector([0.1, 0.4]), DefaultVector([0.65, 0.9]));
+    let mut found = Vec::new();
+    tree.for_each_in_area(query, |_, _, i| found.push(*i));
+    let expected = sequential
+        .query_area(query)
+        .iter()
+        .map(|(.., i)| **i)
+        .collect();
+    assert_eq!(sorted(found), sorted(expected));
+}
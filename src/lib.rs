@@ -1,2 +1,40 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod aabb;
+mod arena;
+#[cfg(feature = "std")]
+mod concurrent;
+mod metadata;
 mod naive;
+mod quadtree;
+mod quadtree2d;
+mod rect;
+mod scalar;
+#[cfg(feature = "serde")]
+mod serde_array;
+mod split;
 mod vector;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use aabb::Aabb;
+pub use arena::{ArenaIter, ArenaTree};
+#[cfg(feature = "std")]
+pub use concurrent::ConcurrentQuadTree;
+pub use metadata::{DefaultMetadata, NodeMetadata};
+pub use naive::{
+    DNode, DepthIter, DotOptions, InsertError, IntoIter, InvalidTree, LeafIter, LeafIterMut,
+    LevelIter, SvgOptions, TreeStats, ValidationError, VisitAction, VisitItem,
+};
+pub use quadtree::{BuildError, DuplicatePolicy, OcTree, QuadTree, QuadTreeBuilder, TryFromError};
+pub use quadtree2d::{QuadTree2D, Quadrant};
+pub use rect::RectQuadTree;
+pub use scalar::Scalar;
+pub use split::{EqualSplit, MidpointSplit, SplitStrategy};
+#[cfg(feature = "cgmath")]
+pub use vector::{CgPoint2, CgPoint3};
+pub use vector::{DefaultVector, IntVector, Vectorial, child_halves};
+#[cfg(feature = "wasm")]
+pub use wasm::WasmQuadTree2D;
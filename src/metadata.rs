@@ -0,0 +1,23 @@
+/// Derives the metadata of interior nodes from the metadata of the points
+/// stored below them, e.g. to keep a total mass or a bounding value on every
+/// node. Only insertions are tracked: removing or relocating points leaves
+/// the metadata of their former ancestors as it was.
+pub trait NodeMetadata<U> {
+    /// Metadata of a node created by splitting a leaf, from the metadata of
+    /// the leaf's first point. The others are passed to `on_insert` right after.
+    fn on_split(leaf_meta: &U) -> U;
+    /// Called on every node a point carrying `leaf_meta` is inserted below.
+    fn on_insert(node_meta: &mut U, leaf_meta: &U);
+}
+
+/// Gives every interior node `U::default()` and leaves it at that.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultMetadata;
+
+impl<U: Default> NodeMetadata<U> for DefaultMetadata {
+    fn on_split(_: &U) -> U {
+        U::default()
+    }
+
+    fn on_insert(_: &mut U, _: &U) {}
+}
@@ -1,75 +1,2072 @@
 /*
 objective: dynamic tree holding points!
 */
+mod display;
+mod dot;
+mod iter;
+pub(crate) mod ray;
+mod stats;
+mod svg;
+mod visit;
+
+use alloc::boxed::Box;
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::{Index, IndexMut};
+
+use crate::aabb::Aabb;
+use crate::metadata::{DefaultMetadata, NodeMetadata};
+use crate::scalar::Scalar;
+use crate::split::{EqualSplit, SplitStrategy};
 use crate::vector::Vectorial;
 
-#[derive(Clone, Debug)]
-enum DNode<const D: usize, T: Vectorial, U, V> {
+pub use dot::DotOptions;
+pub use iter::{DepthIter, IntoIter, LeafIter, LeafIterMut, LevelIter};
+pub use stats::TreeStats;
+pub use svg::SvgOptions;
+pub use visit::{VisitAction, VisitItem};
+
+pub(crate) use ray::Ray;
+
+/// Trees compare equal when they have the same shape and hold the same
+/// points in the same order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: serde::Serialize, U: serde::Serialize, V: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>, U: serde::Deserialize<'de>, V: serde::Deserialize<'de>"
+    ))
+)]
+pub enum DNode<const D: usize, T: Vectorial, U, V> {
+    None,
     Node {
-        area: (T, T),
+        area: Aabb<T>,
+        /// Maintained by the tree's [`NodeMetadata`] hooks.
         metadata: U,
-        data: V,
-        children: [Box<Option<Self>>; D],
+        /// Number of points stored below this node.
+        len: usize,
+        /// Areas of the child slots, as chosen by the split strategy.
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+        cells: [Aabb<T>; D],
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+        children: [Box<Self>; D],
     },
+    /// Bucket of points, never empty.
     Leaf {
-        area: (T, T),
-        position: T,
-        metadata: U,
-        data: V,
+        area: Aabb<T>,
+        points: Vec<(T, U, V)>,
     },
 }
 
-impl<const D: usize, T: Vectorial, U: Clone, V: Clone> DNode<D, T, U, V> {
-    pub fn insert(&mut self, n: &DNode<D, T, U, V>) {
-        if let DNode::Leaf {
-            area: _,
-            position: npos,
-            ..
-        } = n
-        {
-            match self {
-                DNode::Node {
-                    area: _,
-                    metadata: _,
-                    data: _,
-                    children,
-                } => {
+/// Points a leaf holds before splitting when using the `DNode` methods
+/// directly, `QuadTree` lets each tree pick its own bucket size.
+const LEAF_CAPACITY: usize = 1;
+
+/// Levels of nodes below the root when using the `DNode` methods directly.
+/// Halving an `f64` extent this often gets past its precision for areas of
+/// ordinary size, so deeper splits would not separate anything anyway.
+const MAX_DEPTH: usize = 64;
+
+/// When leaves get split: once they hold more than `capacity` points, as long
+/// as they sit less than `max_depth` levels below the root.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Limits {
+    pub(crate) capacity: usize,
+    pub(crate) max_depth: usize,
+}
+
+const LIMITS: Limits = Limits {
+    capacity: LEAF_CAPACITY,
+    max_depth: MAX_DEPTH,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InsertError {
+    /// The position lies outside of the area covered by the tree.
+    OutOfBounds,
+    /// A coordinate of the position is infinite or `NaN`.
+    NonFinite,
+    /// A point is already stored at the position and the tree was set up with
+    /// [`DuplicatePolicy::Reject`](crate::DuplicatePolicy::Reject).
+    Duplicate,
+    /// `DNode::insert` was handed `DNode::None`, which holds nothing to insert.
+    InsertedNone,
+    /// `DNode::insert` was handed a `DNode::Node` instead of a leaf.
+    InsertedNode,
+}
+
+impl core::fmt::Display for InsertError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InsertError::OutOfBounds => write!(f, "position is outside of the tree area"),
+            InsertError::NonFinite => write!(f, "position has a non-finite coordinate"),
+            InsertError::Duplicate => write!(f, "a point is already stored at the position"),
+            InsertError::InsertedNone => write!(f, "cannot insert DNode::None"),
+            InsertError::InsertedNode => write!(f, "cannot insert DNode::Node, only leaves"),
+        }
+    }
+}
+
+impl core::error::Error for InsertError {}
+
+/// Why [`DNode::validate`] rejected a tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidTree {
+    /// A leaf without any points, those should be `DNode::None`.
+    EmptyLeaf,
+    /// A point lies outside of the area of its leaf.
+    PointOutsideLeaf,
+    /// A node without any children, those should be `DNode::None`.
+    EmptyNode,
+    /// A cell of a node reaches past the node's own area.
+    CellOutsideNode,
+    /// The cells of a node overlap by more than their boundaries.
+    OverlappingCells,
+    /// A child's area differs from the cell of its parent it sits in.
+    ChildOutsideCell,
+    /// A node's cached point count differs from the points below it.
+    CountMismatch,
+}
+
+impl core::fmt::Display for InvalidTree {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidTree::EmptyLeaf => write!(f, "leaf without points"),
+            InvalidTree::PointOutsideLeaf => write!(f, "point outside of its leaf's area"),
+            InvalidTree::EmptyNode => write!(f, "node without children"),
+            InvalidTree::CellOutsideNode => write!(f, "cell outside of its node's area"),
+            InvalidTree::OverlappingCells => write!(f, "cells of a node overlap"),
+            InvalidTree::ChildOutsideCell => write!(f, "child area differs from its cell"),
+            InvalidTree::CountMismatch => write!(f, "cached point count is off"),
+        }
+    }
+}
+
+impl core::error::Error for InvalidTree {}
+
+/// The first violation [`DNode::validate`] found and where.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    pub invariant: InvalidTree,
+    /// Child indices leading from the root to the offending node, empty for
+    /// the root itself. Its length is the depth of the node.
+    pub path: Vec<usize>,
+}
+
+impl core::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{} at the root", self.invariant)
+        } else {
+            write!(f, "{} at child path {:?}", self.invariant, self.path)
+        }
+    }
+}
+
+impl core::error::Error for ValidationError {}
+
+impl<const D: usize, T: Vectorial, U, V> DNode<D, T, U, V> {
+    /// Inserts the points of the leaf `n`, rejecting positions outside of this
+    /// node's area (or of `n`'s own area when inserting into `DNode::None`).
+    /// Nothing is inserted when `n` is not a leaf. The points are moved into
+    /// the tree, so neither metadata nor data needs to be `Clone`.
+    pub fn insert(&mut self, n: DNode<D, T, U, V>) -> Result<(), InsertError>
+    where
+        U: Default,
+    {
+        let (area, points) = match n {
+            DNode::None => return Err(InsertError::InsertedNone),
+            DNode::Node { .. } => return Err(InsertError::InsertedNode),
+            DNode::Leaf { area, points } => (area, points),
+        };
+        if !points.iter().all(|(p, ..)| p.is_finite()) {
+            return Err(InsertError::NonFinite);
+        }
+        let bounds = self.area().unwrap_or(area);
+        if !points.iter().all(|(p, ..)| p.within(bounds)) {
+            return Err(InsertError::OutOfBounds);
+        }
+        for point in points {
+            self.insert_point::<EqualSplit, DefaultMetadata>(bounds, point, LIMITS, 0)?;
+        }
+        Ok(())
+    }
+
+    /// Inserts the points of every leaf in `iter`. Stops at the first leaf
+    /// [`DNode::insert`] would reject, keeping the leaves before it.
+    pub fn extend<I>(&mut self, iter: I) -> Result<(), InsertError>
+    where
+        I: IntoIterator<Item = DNode<D, T, U, V>>,
+        U: Default,
+    {
+        iter.into_iter().try_for_each(|n| self.insert(n))
+    }
+
+    /// Inserts a single point into a tree that already covers an area. An
+    /// empty `DNode::None` covers none, so there every position is out of
+    /// bounds; start it off with [`DNode::insert`] or use
+    /// [`QuadTree`](crate::QuadTree), which knows its bounds up front. The
+    /// tree is left untouched on errors.
+    pub fn try_insert(&mut self, position: T, metadata: U, data: V) -> Result<(), InsertError>
+    where
+        U: Default,
+    {
+        if !position.is_finite() {
+            return Err(InsertError::NonFinite);
+        }
+        let bounds = self.area().ok_or(InsertError::OutOfBounds)?;
+        self.insert_point::<EqualSplit, DefaultMetadata>(
+            bounds,
+            (position, metadata, data),
+            LIMITS,
+            0,
+        )
+    }
+
+    /// Inserts a single point into a node covering `area`, `depth` levels below
+    /// the root. Leaves split into the cells chosen by `S` according to
+    /// `limits`, unless all of their points share one position so that no
+    /// split could ever separate them. `M` keeps the metadata of every node
+    /// passed on the way down up to date.
+    pub(crate) fn insert_point<S: SplitStrategy<D, T>, M: NodeMetadata<U>>(
+        &mut self,
+        area: Aabb<T>,
+        point: (T, U, V),
+        limits: Limits,
+        depth: usize,
+    ) -> Result<(), InsertError> {
+        if !point.0.within(area) {
+            return Err(InsertError::OutOfBounds);
+        }
+        match self {
+            DNode::None => {
+                *self = DNode::Leaf {
+                    area,
+                    points: vec![point],
+                }
+            }
+            DNode::Leaf { points, .. } => {
+                points.push(point);
+                if overfull(points, limits, depth) {
+                    self.split::<S, M>(limits, depth);
+                }
+            }
+            DNode::Node {
+                metadata,
+                cells,
+                children,
+                len,
+                ..
+            } => {
+                let i = route(cells, &point.0).ok_or(InsertError::OutOfBounds)?;
+                // Routing succeeded, so the point lies within the child and
+                // can't be rejected any further down.
+                M::on_insert(metadata, &point.1);
+                children[i].insert_point::<S, M>(cells[i], point, limits, depth + 1)?;
+                *len += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Promotes an overflowing leaf to a node and spreads its points over the children.
+    fn split<S: SplitStrategy<D, T>, M: NodeMetadata<U>>(&mut self, limits: Limits, depth: usize) {
+        let DNode::Leaf { area, points } = self else {
+            return;
+        };
+        let positions: Vec<T> = points.iter().map(|(p, ..)| *p).collect();
+        let mut metadata = M::on_split(&points[0].1);
+        for (_, leaf_meta, _) in &points[1..] {
+            M::on_insert(&mut metadata, leaf_meta);
+        }
+        let node = DNode::Node {
+            area: *area,
+            metadata,
+            len: points.len(),
+            cells: S::split(area, &positions),
+            children: core::array::from_fn(|_| Box::new(DNode::None)),
+        };
+        let DNode::Leaf { points, .. } = core::mem::replace(self, node) else {
+            unreachable!()
+        };
+        let DNode::Node {
+            cells, children, ..
+        } = self
+        else {
+            unreachable!()
+        };
+        for point in points {
+            let i = route(cells, &point.0).expect("the cells tile the leaf's area");
+            children[i]
+                .insert_point::<S, M>(cells[i], point, limits, depth + 1)
+                .expect("the points of a leaf lie within its area");
+        }
+    }
+
+    /// Builds the subtree covering `area` holding `points`, `depth` levels
+    /// below the root, by partitioning the points top-down. Nodes appear where
+    /// inserting the points one by one would have split a leaf, so with
+    /// [`EqualSplit`] the shape doesn't depend on the order of `points`. All
+    /// of them have to lie within `area`.
+    pub(crate) fn build<S: SplitStrategy<D, T>, M: NodeMetadata<U>>(
+        area: Aabb<T>,
+        points: Vec<(T, U, V)>,
+        limits: Limits,
+        depth: usize,
+    ) -> Self {
+        if points.is_empty() {
+            return DNode::None;
+        }
+        if !overfull(&points, limits, depth) {
+            return DNode::Leaf { area, points };
+        }
+        let positions: Vec<T> = points.iter().map(|(p, ..)| *p).collect();
+        let cells = S::split(&area, &positions);
+        Self::build_node::<S, M>(area, cells, points, limits, depth)
+    }
+
+    /// Node over `area` split into `cells`, spreading `points` (not empty)
+    /// over subtrees built by [`DNode::build`].
+    fn build_node<S: SplitStrategy<D, T>, M: NodeMetadata<U>>(
+        area: Aabb<T>,
+        cells: [Aabb<T>; D],
+        points: Vec<(T, U, V)>,
+        limits: Limits,
+        depth: usize,
+    ) -> Self {
+        let mut metadata = M::on_split(&points[0].1);
+        for (_, leaf_meta, _) in &points[1..] {
+            M::on_insert(&mut metadata, leaf_meta);
+        }
+        let len = points.len();
+        let mut buckets: [Vec<_>; D] = core::array::from_fn(|_| Vec::new());
+        for point in points {
+            let i = route(&cells, &point.0).expect("the cells tile the area");
+            buckets[i].push(point);
+        }
+        let mut cell = cells.iter();
+        let children = buckets.map(|bucket| {
+            let cell = *cell.next().expect("one cell per bucket");
+            Box::new(Self::build::<S, M>(cell, bucket, limits, depth + 1))
+        });
+        DNode::Node {
+            area,
+            metadata,
+            len,
+            cells,
+            children,
+        }
+    }
+
+    /// Like [`DNode::build`] for `points` already ordered by the cell they
+    /// fall into on each of the next `sorted_levels` levels, as sorting by
+    /// [`DefaultVector::morton_code`](crate::DefaultVector::morton_code) does
+    /// under [`EqualSplit`]. The points of each child are then a run found by
+    /// binary search instead of routing every point. Deeper levels sort their
+    /// points by cell first, which stays cheap as they are mostly in order.
+    pub(crate) fn build_sorted<S: SplitStrategy<D, T>, M: NodeMetadata<U>>(
+        area: Aabb<T>,
+        mut points: Vec<(T, U, V)>,
+        limits: Limits,
+        depth: usize,
+        sorted_levels: usize,
+    ) -> Self {
+        if points.is_empty() {
+            return DNode::None;
+        }
+        if !overfull(&points, limits, depth) {
+            return DNode::Leaf { area, points };
+        }
+        let positions: Vec<T> = points.iter().map(|(p, ..)| *p).collect();
+        let cells = S::split(&area, &positions);
+        let mut metadata = M::on_split(&points[0].1);
+        for (_, leaf_meta, _) in &points[1..] {
+            M::on_insert(&mut metadata, leaf_meta);
+        }
+        let len = points.len();
+        let cell_of = |point: &(T, U, V)| route(&cells, &point.0).expect("the cells tile the area");
+        if sorted_levels == 0 {
+            points.sort_by_key(cell_of);
+        }
+        let mut children: [Box<Self>; D] = core::array::from_fn(|_| Box::new(DNode::None));
+        for i in (0..D).rev() {
+            let start = points.partition_point(|point| cell_of(point) < i);
+            let run = points.split_off(start);
+            *children[i] = Self::build_sorted::<S, M>(
+                cells[i],
+                run,
+                limits,
+                depth + 1,
+                sorted_levels.saturating_sub(1),
+            );
+        }
+        DNode::Node {
+            area,
+            metadata,
+            len,
+            cells,
+            children,
+        }
+    }
+
+    /// Rebalances the subtree below `self`, which lies `depth` levels below
+    /// the root, for points clustered along one axis: rebuilds it as a node
+    /// split in two at the median of its points along the longest axis of
+    /// their bounding box, instead of at the midpoint of every axis. The two
+    /// halves span the area along the other axes and take the children whose
+    /// index has every other bit set, which routing always picks; the
+    /// remaining cells are flat slices of the area's lower faces that stay
+    /// empty. The halves are rebuilt from scratch with `S` and `M` under the
+    /// limits of [`DNode::try_insert`], nodes above `self` are left alone.
+    /// Nothing changes when all points share one position.
+    pub fn split_at_median<S: SplitStrategy<D, T>, M: NodeMetadata<U>>(&mut self, depth: usize) {
+        self.split_at_median_in::<S, M>(LIMITS, depth);
+    }
+
+    pub(crate) fn split_at_median_in<S: SplitStrategy<D, T>, M: NodeMetadata<U>>(
+        &mut self,
+        limits: Limits,
+        depth: usize,
+    ) {
+        let (Some(area), Some(bounding_box)) = (self.area(), self.bounding_box()) else {
+            return;
+        };
+        let (lo, hi) = (bounding_box.min(), bounding_box.max());
+        let extent = |k: usize| (hi.component(k) - lo.component(k)).to_f64();
+        let axes = D.trailing_zeros() as usize;
+        let Some(axis) = (0..axes)
+            .filter(|&k| extent(k) > 0.0)
+            .max_by(|&a, &b| extent(a).total_cmp(&extent(b)))
+        else {
+            return;
+        };
+        let points: Vec<_> = self.drain().collect();
+        let mut values: Vec<_> = points.iter().map(|(p, ..)| p.component(axis)).collect();
+        let mid = values.len() / 2;
+        let (_, median, _) =
+            values.select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        // Where `T` puts the faces of both halves along `axis`.
+        let at = area.min().with_component(axis, *median);
+        let (lower, upper) = (
+            T::split_area_at(area, at, 0).max(),
+            T::split_area_at(area, at, D - 1).min(),
+        );
+        let cells = core::array::from_fn(|i| {
+            let (mut min, mut max) = (area.min(), area.max());
+            for k in 0..axes {
+                let upper_half = i >> k & 1 == 1;
+                if k == axis && upper_half {
+                    min = min.with_component(k, upper.component(k));
+                } else if k == axis {
+                    max = max.with_component(k, lower.component(k));
+                } else if !upper_half {
+                    max = max.with_component(k, min.component(k));
+                }
+            }
+            Aabb::new(min, max)
+        });
+        *self = Self::build_node::<S, M>(area, cells, points, limits, depth);
+    }
+
+    /// Removes a point stored at exactly `position` and hands back its data.
+    /// The tree is left untouched when there is no such point.
+    pub fn remove(&mut self, position: &T) -> Option<V>
+    where
+        T: PartialEq,
+    {
+        self.remove_point(position, LEAF_CAPACITY)
+            .map(|(_, _, data)| data)
+    }
+
+    pub(crate) fn remove_point(&mut self, position: &T, capacity: usize) -> Option<(T, U, V)>
+    where
+        T: PartialEq,
+    {
+        match self {
+            DNode::None => None,
+            DNode::Leaf { points, .. } => {
+                let i = points.iter().position(|(p, ..)| p == position)?;
+                let point = points.swap_remove(i);
+                if points.is_empty() {
+                    *self = DNode::None;
+                }
+                Some(point)
+            }
+            DNode::Node {
+                cells,
+                children,
+                len,
+                ..
+            } => {
+                let i = route(cells, position)?;
+                let point = children[i].remove_point(position, capacity)?;
+                *len -= 1;
+                self.collapse(capacity);
+                Some(point)
+            }
+        }
+    }
+
+    /// Moves the point at `old_position` to `new_position`, keeping its payload.
+    /// The point is updated in place when it would still be routed to the same
+    /// leaf, otherwise it is detached and reinserted below the deepest node both
+    /// positions share. Returns `false`, leaving the tree untouched, when there
+    /// is no point at `old_position` or `new_position` is outside of the tree.
+    pub fn relocate(&mut self, old_position: &T, new_position: T) -> bool
+    where
+        T: PartialEq,
+        U: Default,
+    {
+        self.relocate_point::<EqualSplit, DefaultMetadata>(old_position, new_position, LIMITS)
+    }
+
+    pub(crate) fn relocate_point<S: SplitStrategy<D, T>, M: NodeMetadata<U>>(
+        &mut self,
+        old_position: &T,
+        new_position: T,
+        limits: Limits,
+    ) -> bool
+    where
+        T: PartialEq,
+    {
+        if !self.area().is_some_and(|area| new_position.within(area)) {
+            return false;
+        }
+        match self.relocate_in::<S, M>(old_position, new_position, true, limits, 0) {
+            Relocated::NotFound => false,
+            Relocated::Done => true,
+            Relocated::Detached(_) => unreachable!("the root always shares both positions"),
+        }
+    }
+
+    /// `shared` tells whether inserting `new_position` from the root would
+    /// reach this node, which lies `depth` levels below the root.
+    fn relocate_in<S: SplitStrategy<D, T>, M: NodeMetadata<U>>(
+        &mut self,
+        old_position: &T,
+        new_position: T,
+        shared: bool,
+        limits: Limits,
+        depth: usize,
+    ) -> Relocated<(T, U, V)>
+    where
+        T: PartialEq,
+    {
+        match self {
+            DNode::None => Relocated::NotFound,
+            DNode::Leaf { points, .. } => {
+                let Some(i) = points.iter().position(|(p, ..)| p == old_position) else {
+                    return Relocated::NotFound;
+                };
+                if shared {
+                    points[i].0 = new_position;
+                    // An overfull bucket of equal positions may have become separable.
+                    if overfull(points, limits, depth) {
+                        self.split::<S, M>(limits, depth);
+                    }
+                    return Relocated::Done;
+                }
+                let mut point = points.swap_remove(i);
+                if points.is_empty() {
+                    *self = DNode::None;
+                }
+                point.0 = new_position;
+                Relocated::Detached(point)
+            }
+            DNode::Node {
+                area,
+                cells,
+                children,
+                len,
+                ..
+            } => {
+                let area = *area;
+                let Some(i) = route(cells, old_position) else {
+                    return Relocated::NotFound;
+                };
+                let follows = shared && route(cells, &new_position) == Some(i);
+                match children[i].relocate_in::<S, M>(
+                    old_position,
+                    new_position,
+                    follows,
+                    limits,
+                    depth + 1,
+                ) {
+                    Relocated::Detached(point) => {
+                        *len -= 1;
+                        self.collapse(limits.capacity);
+                        if !shared {
+                            return Relocated::Detached(point);
+                        }
+                        self.insert_point::<S, M>(area, point, limits, depth)
+                            .expect("the new position lies within this node");
+                        Relocated::Done
+                    }
+                    other => other,
+                }
+            }
+        }
+    }
+
+    /// Keeps only the points for which `f` returns `true`, merging nodes the
+    /// same way removing the other points one by one would. Like
+    /// `Vec::retain`, `f` sees every point exactly once, in iteration order.
+    pub fn retain<F: FnMut(&T, &U, &V) -> bool>(&mut self, mut f: F) {
+        self.retain_points(&mut f, LEAF_CAPACITY);
+    }
+
+    /// Returns how many points were dropped.
+    pub(crate) fn retain_points<F: FnMut(&T, &U, &V) -> bool>(
+        &mut self,
+        f: &mut F,
+        capacity: usize,
+    ) -> usize {
+        match self {
+            DNode::None => 0,
+            DNode::Leaf { points, .. } => {
+                let before = points.len();
+                points.retain(|(p, metadata, data)| f(p, metadata, data));
+                let dropped = before - points.len();
+                if points.is_empty() {
+                    *self = DNode::None;
+                }
+                dropped
+            }
+            DNode::Node { children, len, .. } => {
+                let dropped: usize = children
+                    .iter_mut()
+                    .map(|c| c.retain_points(f, capacity))
+                    .sum();
+                *len -= dropped;
+                self.collapse(capacity);
+                dropped
+            }
+        }
+    }
+
+    /// Merges a node whose children are all leaves back into a single leaf
+    /// when their points fit into one (or can't be separated anyway), and
+    /// turns a node without any points left into `None`.
+    fn collapse(&mut self, capacity: usize) {
+        let DNode::Node { area, children, .. } = self else {
+            return;
+        };
+        let (mut total, mut first, mut same) = (0, None, true);
+        for child in children.iter() {
+            match &**child {
+                DNode::None => {}
+                DNode::Leaf { points, .. } => {
+                    let f = *first.get_or_insert(points[0].0);
+                    total += points.len();
+                    same &= points.iter().all(|(p, ..)| p.within(Aabb::new(f, f)));
+                }
+                DNode::Node { .. } => return,
+            }
+        }
+        if total > capacity && !same {
+            return;
+        }
+        let area = *area;
+        let points: Vec<_> = children
+            .iter_mut()
+            .flat_map(|c| match core::mem::replace(&mut **c, DNode::None) {
+                DNode::Leaf { points, .. } => points,
+                _ => Vec::new(),
+            })
+            .collect();
+        *self = if points.is_empty() {
+            DNode::None
+        } else {
+            DNode::Leaf { area, points }
+        };
+    }
+}
+
+/// Whether a leaf holding `points` at `depth` has to be split.
+pub(crate) fn overfull<T: Vectorial, U, V>(
+    points: &[(T, U, V)],
+    limits: Limits,
+    depth: usize,
+) -> bool {
+    points.len() > limits.capacity && depth < limits.max_depth && !inseparable(points)
+}
+
+/// Index of the cell a point at `position` belongs to, `None` when it lies
+/// outside of all of them. Cells are half-open per axis, so a point on a
+/// split plane goes to the upper cell, only the maximum faces of the node
+/// itself are closed. As points on the maximum faces of a node can only
+/// have reached it if they are also on the root's, every point of the root
+/// area ends up in exactly one leaf.
+pub(crate) fn route<const D: usize, T: Vectorial>(
+    cells: &[Aabb<T>; D],
+    position: &T,
+) -> Option<usize> {
+    if !position.within(Aabb::new(cells[0].min(), cells[D - 1].max())) {
+        return None;
+    }
+    // The upper cell along every axis starts at the split point.
+    let at = cells[D - 1].min();
+    Some((0..D.trailing_zeros() as usize).fold(0, |i, k| {
+        i | usize::from(position.component(k) >= at.component(k)) << k
+    }))
+}
+
+/// Whether all points share a single position.
+pub(crate) fn inseparable<T: Vectorial, U, V>(points: &[(T, U, V)]) -> bool {
+    let first = points[0].0;
+    points
+        .iter()
+        .all(|(p, ..)| p.within(Aabb::new(first, first)))
+}
+
+impl<const D: usize, T: Vectorial, U, V> DNode<D, T, U, V> {
+    /// Number of points stored in this subtree, read off the cached count of a node.
+    pub fn len(&self) -> usize {
+        match self {
+            DNode::None => 0,
+            DNode::Leaf { points, .. } => points.len(),
+            DNode::Node { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, DNode::None)
+    }
+
+    /// Area covered by this node, `None` for `DNode::None`.
+    pub fn area(&self) -> Option<Aabb<T>> {
+        match self {
+            DNode::None => None,
+            DNode::Node { area, .. } | DNode::Leaf { area, .. } => Some(*area),
+        }
+    }
+
+    /// Smallest box enclosing every stored position, unlike [`DNode::area`]
+    /// which is the region the node covers.
+    pub fn bounding_box(&self) -> Option<Aabb<T>> {
+        self.iter()
+            .map(|(p, ..)| Aabb::new(*p, *p))
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// Mean of every stored position, `None` when there are none.
+    pub fn centroid(&self) -> Option<T> {
+        crate::vector::mean(self.iter().map(|(p, ..)| p), D.trailing_zeros() as usize)
+    }
+
+    /// Metadata of an interior node, or of the first point of a leaf.
+    pub fn metadata(&self) -> Option<&U> {
+        match self {
+            DNode::None => None,
+            DNode::Node { metadata, .. } => Some(metadata),
+            DNode::Leaf { points, .. } => Some(&points[0].1),
+        }
+    }
+
+    /// Data of the first point of a leaf, `None` for anything else.
+    pub fn data(&self) -> Option<&V> {
+        match self {
+            DNode::Leaf { points, .. } => Some(&points[0].2),
+            _ => None,
+        }
+    }
+
+    /// Drops every stored point, leaving `DNode::None` behind.
+    pub fn clear(&mut self) {
+        *self = DNode::None;
+    }
+
+    /// Number of interior nodes on the longest path down to a leaf. Walks
+    /// the tree with its own stack, so deep trees don't overflow the call
+    /// stack.
+    pub fn depth(&self) -> usize {
+        let mut deepest: usize = 0;
+        let mut stack = vec![(0, self)];
+        while let Some((above, node)) = stack.pop() {
+            if let DNode::Node { children, .. } = node {
+                deepest = deepest.max(above + 1);
+                stack.extend(children.iter().map(|child| (above + 1, &**child)));
+            }
+        }
+        deepest
+    }
+
+    /// Whether a point is stored at exactly `position`. Only the one child the
+    /// position routes to is searched on each level.
+    pub fn contains(&self, position: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        match self {
+            DNode::None => false,
+            DNode::Leaf { points, .. } => points.iter().any(|(p, ..)| p == position),
+            DNode::Node {
+                cells, children, ..
+            } => route(cells, position).is_some_and(|i| children[i].contains(position)),
+        }
+    }
+
+    /// Applies `f` to the metadata of a point stored at exactly `position`,
+    /// found the same way as by [`DNode::contains`]. Returns `false` when
+    /// there is no such point.
+    pub fn update_metadata<F: FnMut(&mut U)>(&mut self, position: &T, mut f: F) -> bool
+    where
+        T: PartialEq,
+    {
+        match self.find_mut(position) {
+            Some((_, metadata, _)) => {
+                f(metadata);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn find_mut(&mut self, position: &T) -> Option<&mut (T, U, V)>
+    where
+        T: PartialEq,
+    {
+        match self {
+            DNode::None => None,
+            DNode::Leaf { points, .. } => points.iter_mut().find(|(p, ..)| p == position),
+            DNode::Node {
+                cells, children, ..
+            } => {
+                let i = route(cells, position)?;
+                children[i].find_mut(position)
+            }
+        }
+    }
+
+    /// Checks the invariants the tree maintains itself, for trees put
+    /// together from outside, e.g. deserialized ones, or in property tests
+    /// after random edits. Splitting and routing are not redone, so any cells
+    /// tiling their node's area are accepted.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_in(&mut Vec::new()).map(|_| ())
+    }
+
+    /// Panics with the first violation [`DNode::validate`] finds, for
+    /// checking trees in tests and debug builds.
+    #[track_caller]
+    pub fn assert_valid(&self) {
+        if let Err(e) = self.validate() {
+            panic!("invalid tree: {e}");
+        }
+    }
+
+    /// Returns the number of points below this node, found at `path`.
+    fn validate_in(&self, path: &mut Vec<usize>) -> Result<usize, ValidationError> {
+        let fail = |invariant, path: &[usize]| {
+            Err(ValidationError {
+                invariant,
+                path: path.to_vec(),
+            })
+        };
+        match self {
+            DNode::None => Ok(0),
+            DNode::Leaf { points, .. } if points.is_empty() => fail(InvalidTree::EmptyLeaf, path),
+            DNode::Leaf { area, points } => {
+                if !points.iter().all(|(p, ..)| p.within(*area)) {
+                    return fail(InvalidTree::PointOutsideLeaf, path);
+                }
+                Ok(points.len())
+            }
+            DNode::Node {
+                area,
+                len,
+                cells,
+                children,
+                ..
+            } => {
+                if children.iter().all(|child| matches!(**child, DNode::None)) {
+                    return fail(InvalidTree::EmptyNode, path);
+                }
+                if !cells
+                    .iter()
+                    .all(|cell| cell.min().within(*area) && cell.max().within(*area))
+                {
+                    return fail(InvalidTree::CellOutsideNode, path);
+                }
+                let axes = D.trailing_zeros() as usize;
+                let overlap = |a: &Aabb<T>, b: &Aabb<T>| {
+                    (0..axes).all(|k| {
+                        let [a_lo, b_lo, a_hi, b_hi] =
+                            [a.min(), b.min(), a.max(), b.max()].map(|v| v.component(k).to_f64());
+                        a_lo.max(b_lo) < a_hi.min(b_hi)
+                    })
+                };
+                if (0..D).any(|i| (i + 1..D).any(|j| overlap(&cells[i], &cells[j]))) {
+                    return fail(InvalidTree::OverlappingCells, path);
+                }
+                let mut total = 0;
+                for (i, (cell, child)) in cells.iter().zip(children).enumerate() {
+                    path.push(i);
+                    let same = |p: T, q: T| p.within(Aabb::new(q, q));
+                    if let Some(area) = child.area()
+                        && !(same(area.min(), cell.min()) && same(area.max(), cell.max()))
+                    {
+                        return fail(InvalidTree::ChildOutsideCell, path);
+                    }
+                    total += child.validate_in(path)?;
+                    path.pop();
+                }
+                if total != *len {
+                    return fail(InvalidTree::CountMismatch, path);
+                }
+                Ok(total)
+            }
+        }
+    }
+
+    /// Every stored point lying within `area`.
+    pub fn query_range(&self, area: Aabb<T>) -> Vec<(&T, &U, &V)> {
+        let mut found = Vec::new();
+        self.collect_range(area, &mut found);
+        found
+    }
+
+    /// Number of stored points lying within `area`. Nodes covered by `area` as
+    /// a whole contribute their cached count without being descended into.
+    pub fn count_in_area(&self, area: Aabb<T>) -> usize {
+        match self {
+            DNode::None => 0,
+            DNode::Leaf { points, .. } => points.iter().filter(|(p, ..)| p.within(area)).count(),
+            DNode::Node {
+                area: node_area,
+                len,
+                children,
+                ..
+            } => {
+                if node_area.min().within(area) && node_area.max().within(area) {
+                    *len
+                } else if T::intersects(*node_area, area) {
+                    children.iter().map(|c| c.count_in_area(area)).sum()
+                } else {
+                    0
+                }
+            }
+        }
+    }
+
+    /// Calls `f` on every stored point lying within `area`, handing out its
+    /// metadata and data to change in place. Interior node metadata is left
+    /// as it was.
+    pub fn for_each_in_range<F: FnMut(&T, &mut U, &mut V)>(&mut self, area: Aabb<T>, mut f: F) {
+        self.visit_range_mut(area, &mut f);
+    }
+
+    fn visit_range_mut<F: FnMut(&T, &mut U, &mut V)>(&mut self, area: Aabb<T>, f: &mut F) {
+        match self {
+            DNode::None => {}
+            DNode::Leaf { points, .. } => {
+                for (position, metadata, data) in points {
+                    if position.within(area) {
+                        f(position, metadata, data);
+                    }
+                }
+            }
+            DNode::Node {
+                area: node_area,
+                children,
+                ..
+            } => {
+                if T::intersects(*node_area, area) {
                     for child in children {
-                        match **child {
-                            Some(DNode::Leaf { area, .. }) | Some(DNode::Node { area, .. }) => {
-                                if npos.within(area) {
-                                    child.insert(n.clone());
-                                    break;
-                                }
-                            }
-                            None => continue,
+                        child.visit_range_mut(area, f);
+                    }
+                }
+            }
+        }
+    }
+
+    fn collect_range<'a>(&'a self, area: Aabb<T>, found: &mut Vec<(&'a T, &'a U, &'a V)>) {
+        match self {
+            DNode::None => {}
+            DNode::Leaf { points, .. } => {
+                for (position, metadata, data) in points {
+                    if position.within(area) {
+                        found.push((position, metadata, data));
+                    }
+                }
+            }
+            DNode::Node {
+                area: node_area,
+                children,
+                ..
+            } => {
+                if T::intersects(*node_area, area) {
+                    for child in children {
+                        child.collect_range(area, found);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every stored point at most `radius` away from `center`, the circle or
+    /// sphere counterpart of [`DNode::query_range`]: the box around the circle
+    /// is queried and its corners filtered out by distance. Finds the same
+    /// points as [`DNode::for_each_within_radius`], which prunes by distance
+    /// instead and skips those corners while descending.
+    pub fn query_radius(&self, center: &T, radius: f64) -> Vec<(&T, &U, &V)> {
+        if radius.is_nan() || radius < 0.0 {
+            return Vec::new();
+        }
+        // Rounding the bounds to the nearest scalar can't drop a stored point
+        // lying on them, as that is a scalar too.
+        let (mut lo, mut hi) = (*center, *center);
+        for k in 0..D.trailing_zeros() as usize {
+            let c = center.component(k).to_f64();
+            lo = lo.with_component(k, T::Scalar::from_f64(c - radius));
+            hi = hi.with_component(k, T::Scalar::from_f64(c + radius));
+        }
+        let mut found = self.query_range(Aabb::new(lo, hi));
+        found.retain(|(position, ..)| position.distance_squared(center) <= radius * radius);
+        found
+    }
+
+    /// Calls `f` on every stored point at most `radius` away from `center`,
+    /// on none when `radius` is negative or `NaN`.
+    pub fn for_each_within_radius<'a, F>(&'a self, center: &T, radius: f64, mut f: F)
+    where
+        F: FnMut(&'a T, &'a U, &'a V),
+    {
+        if radius.is_nan() || radius < 0.0 {
+            return;
+        }
+        self.visit_radius(center, radius * radius, &mut f);
+    }
+
+    fn visit_radius<'a, F>(&'a self, center: &T, radius_sq: f64, f: &mut F)
+    where
+        F: FnMut(&'a T, &'a U, &'a V),
+    {
+        match self {
+            DNode::None => {}
+            DNode::Leaf { points, .. } => {
+                for (position, metadata, data) in points {
+                    if position.distance_squared(center) <= radius_sq {
+                        f(position, metadata, data);
+                    }
+                }
+            }
+            DNode::Node { area, children, .. } => {
+                if center.min_distance_squared_to_area(*area) <= radius_sq {
+                    for child in children {
+                        child.visit_radius(center, radius_sq, f);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The stored point closest to `point`, `None` when the tree is empty.
+    pub fn nearest(&self, point: &T) -> Option<(&T, &U, &V)> {
+        let (mut best, mut best_dist) = (None, f64::INFINITY);
+        self.nearest_in(point, &mut best, &mut best_dist);
+        best
+    }
+
+    fn nearest_in<'a>(
+        &'a self,
+        point: &T,
+        best: &mut Option<(&'a T, &'a U, &'a V)>,
+        best_dist: &mut f64,
+    ) {
+        match self {
+            DNode::None => {}
+            DNode::Leaf { points, .. } => {
+                for (position, metadata, data) in points {
+                    let dist = position.distance_squared(point);
+                    if dist < *best_dist {
+                        *best_dist = dist;
+                        *best = Some((position, metadata, data));
+                    }
+                }
+            }
+            DNode::Node { area, children, .. } => {
+                // Nothing in here can beat the current best.
+                if point.min_distance_squared_to_area(*area) >= *best_dist {
+                    return;
+                }
+                for (dist, i) in Self::by_distance(children, point) {
+                    if dist >= *best_dist {
+                        break;
+                    }
+                    children[i].nearest_in(point, best, best_dist);
+                }
+            }
+        }
+    }
+
+    /// The `k` stored points closest to `point`, nearest first.
+    pub fn k_nearest(&self, point: &T, k: usize) -> Vec<(&T, &U, &V)> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        self.k_nearest_in(point, k, &mut heap);
+        heap.into_sorted_vec().into_iter().map(|c| c.item).collect()
+    }
+
+    fn k_nearest_in<'a>(
+        &'a self,
+        point: &T,
+        k: usize,
+        heap: &mut BinaryHeap<Candidate<(&'a T, &'a U, &'a V)>>,
+    ) {
+        // Worst distance still worth looking at once the heap is full.
+        let bound = |heap: &BinaryHeap<Candidate<_>>| match heap.peek() {
+            Some(worst) if heap.len() == k => worst.dist,
+            _ => f64::INFINITY,
+        };
+        match self {
+            DNode::None => {}
+            DNode::Leaf { points, .. } => {
+                for (position, metadata, data) in points {
+                    let dist = position.distance_squared(point);
+                    if dist < bound(heap) {
+                        if heap.len() == k {
+                            heap.pop();
                         }
+                        heap.push(Candidate {
+                            dist,
+                            item: (position, metadata, data),
+                        });
                     }
                 }
-                DNode::Leaf {
-                    area,
-                    position,
-                    metadata,
-                    data,
-                } => {
-                    *self = DNode::Node {
-                        // FIXME: transition function from leaf to node
-                        area: *area,
-                        metadata: metadata.clone(),
-                        data: data.clone(),
-                        children: std::array::from_fn::<_, D, _>(|i| {
-                            if position.within(area.quarter(i)) {
-                                // FIXME: How to set area to quarter here?
-                                Some(self.clone())
-                            } else {
-                                None
-                            }
-                        }),
+            }
+            DNode::Node { area, children, .. } => {
+                if point.min_distance_squared_to_area(*area) >= bound(heap) {
+                    return;
+                }
+                for (dist, i) in Self::by_distance(children, point) {
+                    if dist >= bound(heap) {
+                        break;
                     }
+                    children[i].k_nearest_in(point, k, heap);
                 }
             }
-        } else {
-            panic!("Trying to insert either DNode::None or DNode::Node.")
         }
     }
+
+    /// Child indices ordered by the distance of their area to `point`, so the
+    /// searches visit the most promising cell first. Empty slots sort last.
+    fn by_distance(children: &[Box<Self>; D], point: &T) -> [(f64, usize); D] {
+        let mut order = core::array::from_fn(|i| {
+            let dist = children[i]
+                .area()
+                .map_or(f64::INFINITY, |a| point.min_distance_squared_to_area(a));
+            (dist, i)
+        });
+        order.sort_by(|a, b| a.0.total_cmp(&b.0));
+        order
+    }
+}
+
+enum Relocated<N> {
+    NotFound,
+    Done,
+    /// The leaf left its cell and still has to be reinserted higher up.
+    Detached(N),
+}
+
+/// Child `i` of an interior node, which may be `DNode::None`.
+///
+/// # Panics
+///
+/// Panics on leaves and `DNode::None`, which have no children, and when
+/// `i` is not below `D`.
+impl<const D: usize, T: Vectorial, U, V> Index<usize> for DNode<D, T, U, V> {
+    type Output = Self;
+
+    fn index(&self, i: usize) -> &Self {
+        match self {
+            DNode::Node { children, .. } => &children[i],
+            DNode::Leaf { .. } => panic!("cannot index into a leaf, only nodes have children"),
+            DNode::None => panic!("cannot index into DNode::None, only nodes have children"),
+        }
+    }
+}
+
+/// See the `Index` impl. Swapping children for ones that don't match their
+/// cells breaks the tree, [`DNode::validate`] catches that.
+impl<const D: usize, T: Vectorial, U, V> IndexMut<usize> for DNode<D, T, U, V> {
+    fn index_mut(&mut self, i: usize) -> &mut Self {
+        match self {
+            DNode::Node { children, .. } => &mut children[i],
+            DNode::Leaf { .. } => panic!("cannot index into a leaf, only nodes have children"),
+            DNode::None => panic!("cannot index into DNode::None, only nodes have children"),
+        }
+    }
+}
+
+/// Max-heap entry ordered by distance only.
+pub(crate) struct Candidate<I> {
+    pub(crate) dist: f64,
+    pub(crate) item: I,
+}
+
+impl<I> PartialEq for Candidate<I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.dist.total_cmp(&other.dist).is_eq()
+    }
+}
+
+impl<I> Eq for Candidate<I> {}
+
+impl<I> PartialOrd for Candidate<I> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<I> Ord for Candidate<I> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dist.total_cmp(&other.dist)
+    }
+}
+
+#[test]
+fn test_index_children() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree = QuadTree::<4, _, (), u32>::with_bucket_size(
+        (DefaultVector([0.0, 0.0]), DefaultVector([4.0, 4.0])),
+        1,
+    );
+    for (i, p) in [[1.0, 1.0], [2.5, 2.5], [3.5, 3.5]].into_iter().enumerate() {
+        tree.insert(DefaultVector(p), (), i as u32).unwrap();
+    }
+    assert!(matches!(tree.root[0], DNode::Leaf { .. }));
+    assert!(matches!(tree.root[1], DNode::None));
+    assert_eq!(tree.root[3][0].data(), Some(&1));
+    assert_eq!(tree.root[3][3].data(), Some(&2));
+    tree.root[3][0] = DNode::None;
+    let error = tree.root.validate().unwrap_err();
+    assert_eq!(error.invariant, InvalidTree::CountMismatch);
+    let leaf = std::panic::catch_unwind(|| tree.root[0][0].len());
+    assert!(leaf.is_err());
+}
+
+#[test]
+fn test_insert_promotes_leaves() {
+    use crate::vector::DefaultVector;
+    let mut tree = unit_tree::<(), usize>(1);
+    // The first two share a quadrant, so promotion has to subdivide twice.
+    let points = [[0.1, 0.1], [0.2, 0.2], [0.9, 0.1], [0.6, 0.8]];
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(DefaultVector(*p), (), i).unwrap();
+    }
+    assert_eq!(tree.root.len(), 4);
+    tree.root.assert_valid();
+
+    // Equal positions can't be told apart by splitting, they share a leaf.
+    tree.insert(DefaultVector([0.2, 0.2]), (), 9).unwrap();
+    assert_eq!(tree.root.len(), 5);
+    tree.root.assert_valid();
+}
+
+#[test]
+fn test_insert_on_split_plane() {
+    use crate::vector::DefaultVector;
+    let mut tree = unit_tree::<(), ()>(1);
+    tree.insert(DefaultVector([0.1, 0.1]), (), ()).unwrap();
+    tree.insert(DefaultVector([0.5, 0.5]), (), ()).unwrap();
+    assert_eq!(tree.root.len(), 2);
+    tree.root.assert_valid();
+    let DNode::Node { children, .. } = &tree.root else {
+        panic!("root should have been promoted");
+    };
+    // Cells are half-open, the centre belongs to the upper one.
+    assert!(matches!(*children[0], DNode::Leaf { .. }));
+    assert!(matches!(*children[3], DNode::Leaf { .. }));
+}
+
+#[test]
+fn test_insert_boundary_grid() {
+    use crate::vector::DefaultVector;
+    let mut tree = unit_tree::<(), usize>(1);
+    // Every point lies on a split plane or on a face of the root.
+    let grid: Vec<_> = (0..=8)
+        .flat_map(|x| (0..=8).map(move |y| DefaultVector([x as f64 / 8.0, y as f64 / 8.0])))
+        .collect();
+    for (i, p) in grid.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    tree.root.assert_valid();
+    let mut found: Vec<_> = tree
+        .query_area(tree.bounds())
+        .iter()
+        .map(|(.., i)| **i)
+        .collect();
+    found.sort();
+    assert_eq!(found, (0..grid.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_remove() {
+    use crate::vector::DefaultVector;
+    let mut tree = unit_tree::<(), usize>(1);
+    let points = [[0.1, 0.1], [0.2, 0.2], [0.9, 0.9]];
+    for (i, p) in points.into_iter().enumerate() {
+        tree.insert(DefaultVector(p), (), i).unwrap();
+    }
+    let before = format!("{:?}", tree.root);
+    assert_eq!(tree.remove(&DefaultVector([0.3, 0.3])), None);
+    assert_eq!(format!("{:?}", tree.root), before);
+    assert_eq!(tree.remove(&DefaultVector([0.2, 0.2])), Some(1));
+    assert_eq!(tree.remove(&DefaultVector([0.2, 0.2])), None);
+    assert_eq!(tree.root.len(), 2);
+    tree.root.assert_valid();
+
+    // The last remaining point collapses all the way up into a root leaf.
+    assert_eq!(tree.remove(&DefaultVector([0.9, 0.9])), Some(2));
+    let DNode::Leaf { area, points } = &tree.root else {
+        panic!("root should have collapsed into a leaf");
+    };
+    assert_eq!((*area, points[0].0), (tree.area, DefaultVector([0.1, 0.1])));
+
+    assert_eq!(tree.remove(&DefaultVector([0.1, 0.1])), Some(0));
+    assert!(matches!(tree.root, DNode::None));
+
+    // Repeated insert/remove cycles don't leave empty interior nodes behind.
+    for _ in 0..3 {
+        for (i, p) in random_points(20, 8).into_iter().enumerate() {
+            tree.insert(p, (), i).unwrap();
+        }
+        for p in random_points(20, 8) {
+            assert!(tree.remove(&p).is_some());
+        }
+        assert!(matches!(tree.root, DNode::None));
+    }
+}
+
+#[test]
+fn test_query_range() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), usize>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    let points = [[0.1, 0.1], [0.2, 0.2], [0.9, 0.1], [0.6, 0.8], [0.4, 0.45]];
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(DefaultVector(*p), (), i).unwrap();
+    }
+    let query = Aabb::new(DefaultVector([0.15, 0.0]), DefaultVector([0.5, 0.5]));
+    let mut found: Vec<_> = tree.root.query_range(query).iter().map(|p| *p.2).collect();
+    found.sort();
+    assert_eq!(found, [1, 4]);
+    assert!(
+        tree.root
+            .query_range(Aabb::new(
+                DefaultVector([2.0, 2.0]),
+                DefaultVector([3.0, 3.0])
+            ))
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_for_each_in_range() {
+    use crate::vector::DefaultVector;
+    let mut tree = unit_tree::<u32, usize>(2);
+    let points = random_points(200, 23);
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, 0, i).unwrap();
+    }
+    let blast = Aabb::new(DefaultVector([0.2, 0.3]), DefaultVector([0.6, 0.5]));
+    let mut hit = 0;
+    tree.root.for_each_in_range(blast, |_, damage, _| {
+        *damage += 10;
+        hit += 1;
+    });
+    assert_eq!(hit, tree.root.count_in_area(blast));
+    for (position, damage, i) in tree.iter() {
+        assert_eq!(*damage == 10, position.within(blast));
+        assert_eq!(*position, points[*i]);
+    }
+}
+
+#[test]
+fn test_insert_out_of_bounds() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), ()>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    let outside = DefaultVector([1.5, 0.5]);
+    assert_eq!(tree.insert(outside, (), ()), Err(InsertError::OutOfBounds));
+    assert!(matches!(tree.root, DNode::None));
+
+    tree.insert(DefaultVector([0.2, 0.2]), (), ()).unwrap();
+    tree.insert(DefaultVector([0.7, 0.7]), (), ()).unwrap();
+    assert_eq!(tree.insert(outside, (), ()), Err(InsertError::OutOfBounds));
+    assert_eq!(tree.root.len(), 2);
+
+    // Points on the outer faces and on the split planes land exactly once.
+    for p in [[1.0, 1.0], [0.5, 0.0], [0.0, 0.5], [0.5, 1.0]] {
+        tree.insert(DefaultVector(p), (), ()).unwrap();
+    }
+    assert_eq!(tree.root.len(), 6);
+    tree.root.assert_valid();
+}
+
+#[test]
+fn test_insert_non_finite() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), ()>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    tree.insert(DefaultVector([0.5, 0.5]), (), ()).unwrap();
+    let before = tree.root.clone();
+    for p in [
+        [f64::NAN, 0.0],
+        [0.2, f64::INFINITY],
+        [f64::NEG_INFINITY, 0.3],
+    ] {
+        assert_eq!(
+            tree.insert(DefaultVector(p), (), ()),
+            Err(InsertError::NonFinite)
+        );
+    }
+    assert_eq!(tree.root, before);
+    assert_eq!(tree.len(), 1);
+
+    let mut node = before.clone();
+    let leaf = DNode::Leaf {
+        area: tree.bounds(),
+        points: vec![
+            (DefaultVector([0.1, 0.1]), (), ()),
+            (DefaultVector([f64::NAN, 0.1]), (), ()),
+        ],
+    };
+    assert_eq!(node.insert(leaf), Err(InsertError::NonFinite));
+    assert_eq!(node, before);
+}
+
+#[test]
+fn test_contains() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), ()>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    let points = random_points(100, 5);
+    assert!(!tree.root.contains(&points[0]));
+    for p in &points[..50] {
+        tree.insert(*p, (), ()).unwrap();
+    }
+    tree.insert(DefaultVector([0.5, 0.5]), (), ()).unwrap();
+    assert!(points[..50].iter().all(|p| tree.root.contains(p)));
+    assert!(!points[50..].iter().any(|p| tree.root.contains(p)));
+    assert!(tree.root.contains(&DefaultVector([0.5, 0.5])));
+    assert!(!tree.root.contains(&DefaultVector([1.5, 0.5])));
+}
+
+#[test]
+fn test_update_metadata() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, u32, usize>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    let points = random_points(100, 31);
+    for (i, p) in points[..50].iter().enumerate() {
+        tree.insert(*p, 0, i).unwrap();
+    }
+    for p in points[..50].iter().step_by(2) {
+        assert!(tree.update_metadata(p, |m| *m += 1));
+    }
+    assert!(!tree.update_metadata(&points[50], |m| *m += 1));
+    for (_, metadata, i) in tree.iter() {
+        assert_eq!(*metadata, u32::from(i % 2 == 0));
+    }
+}
+
+#[test]
+fn test_insert_rejects_non_leaves() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut node = DNode::<4, _, (), ()>::None;
+    assert_eq!(node.insert(DNode::None), Err(InsertError::InsertedNone));
+    let mut other = DNode::<4, _, (), ()>::None;
+    other
+        .insert(DNode::Leaf {
+            area,
+            points: vec![(DefaultVector([0.2, 0.2]), (), ())],
+        })
+        .unwrap();
+    other.try_insert(DefaultVector([0.7, 0.7]), (), ()).unwrap();
+    assert!(matches!(other, DNode::Node { .. }));
+    assert_eq!(node.insert(other), Err(InsertError::InsertedNode));
+    assert!(node.is_empty());
+}
+
+#[test]
+fn test_try_insert() {
+    use crate::vector::DefaultVector;
+    let mut node = DNode::<4, _, (), u32>::None;
+    assert_eq!(
+        node.try_insert(DefaultVector([0.5, 0.5]), (), 0),
+        Err(InsertError::OutOfBounds)
+    );
+    node.insert(DNode::Leaf {
+        area: Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])),
+        points: vec![(DefaultVector([0.2, 0.2]), (), 0)],
+    })
+    .unwrap();
+    let before = node.clone();
+    assert_eq!(
+        node.try_insert(DefaultVector([1.2, 0.5]), (), 1),
+        Err(InsertError::OutOfBounds)
+    );
+    assert_eq!(
+        node.try_insert(DefaultVector([f64::NAN, 0.5]), (), 1),
+        Err(InsertError::NonFinite)
+    );
+    assert_eq!(node, before);
+    node.try_insert(DefaultVector([0.8, 0.5]), (), 1).unwrap();
+    assert_eq!(node.len(), 2);
+    node.assert_valid();
+}
+
+/// The unit square [`random_points`] draws from.
+#[cfg(test)]
+pub(crate) const UNIT_SQUARE: (
+    crate::vector::DefaultVector<2>,
+    crate::vector::DefaultVector<2>,
+) = (
+    crate::vector::DefaultVector([0.0, 0.0]),
+    crate::vector::DefaultVector([1.0, 1.0]),
+);
+
+/// An empty tree over [`UNIT_SQUARE`].
+#[cfg(test)]
+pub(crate) fn unit_tree<U: Default, V>(
+    bucket_size: usize,
+) -> crate::QuadTree<4, crate::vector::DefaultVector<2>, U, V> {
+    crate::QuadTree::with_bucket_size(UNIT_SQUARE, bucket_size)
+}
+
+/// A [`unit_tree`] holding `random_points(n, seed)` with their indices as
+/// data, along with those points.
+#[cfg(test)]
+pub(crate) fn random_tree(
+    n: usize,
+    seed: u64,
+    bucket_size: usize,
+) -> (
+    crate::QuadTree<4, crate::vector::DefaultVector<2>, (), usize>,
+    Vec<crate::vector::DefaultVector<2>>,
+) {
+    let mut tree = unit_tree(bucket_size);
+    let points = random_points(n, seed);
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    (tree, points)
+}
+
+/// `items` in ascending order, for results that come in no particular one.
+#[cfg(test)]
+pub(crate) fn sorted<X: Ord>(mut items: Vec<X>) -> Vec<X> {
+    items.sort();
+    items
+}
+
+#[cfg(test)]
+pub(crate) fn random_points(n: usize, mut seed: u64) -> Vec<crate::vector::DefaultVector<2>> {
+    let mut next = move || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    };
+    (0..n)
+        .map(|_| crate::vector::DefaultVector([next(), next()]))
+        .collect()
+}
+
+#[test]
+fn test_nearest() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), usize>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    assert!(tree.root.nearest(&DefaultVector([0.5, 0.5])).is_none());
+
+    let points = random_points(200, 1);
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    for target in random_points(50, 2) {
+        let expected = (0..points.len())
+            .min_by(|&a, &b| {
+                let (da, db) = (
+                    points[a].distance_squared(&target),
+                    points[b].distance_squared(&target),
+                );
+                da.total_cmp(&db)
+            })
+            .unwrap();
+        let (_, _, found) = tree.root.nearest(&target).unwrap();
+        assert_eq!(*found, expected);
+    }
+}
+
+#[test]
+fn test_k_nearest() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), usize>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    let target = DefaultVector([0.3, 0.6]);
+    assert!(tree.root.k_nearest(&target, 3).is_empty());
+
+    let points = random_points(300, 3);
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    let mut expected: Vec<usize> = (0..points.len()).collect();
+    expected.sort_by(|&a, &b| {
+        points[a]
+            .distance_squared(&target)
+            .total_cmp(&points[b].distance_squared(&target))
+    });
+
+    let found: Vec<usize> = tree
+        .root
+        .k_nearest(&target, 10)
+        .iter()
+        .map(|p| *p.2)
+        .collect();
+    assert_eq!(found, expected[..10]);
+    assert!(tree.root.k_nearest(&target, 0).is_empty());
+    assert_eq!(tree.root.k_nearest(&target, 1000).len(), 300);
+}
+
+#[test]
+fn test_promotion_fills_matching_slots() {
+    use crate::vector::DefaultVector;
+    let mut tree = unit_tree::<(), char>(1);
+    tree.insert(DefaultVector([0.75, 0.25]), (), 'a').unwrap();
+    tree.insert(DefaultVector([0.25, 0.75]), (), 'b').unwrap();
+    let DNode::Node { children, .. } = &tree.root else {
+        panic!("root should have been promoted");
+    };
+    let slots: Vec<_> = children
+        .iter()
+        .map(|c| match **c {
+            DNode::Leaf { area, ref points } => Some((*area.min(), *area.max(), points[0].2)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        slots,
+        [
+            None,
+            Some(([0.5, 0.0], [1.0, 0.5], 'a')),
+            Some(([0.0, 0.5], [0.5, 1.0], 'b')),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn test_len() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut node = DNode::<4, _, (), ()>::None;
+    assert_eq!(node.len(), 0);
+    assert!(node.is_empty());
+    for (i, p) in random_points(10, 7).into_iter().enumerate() {
+        let leaf = DNode::Leaf {
+            area,
+            points: vec![(p, (), ())],
+        };
+        node.insert(leaf).unwrap();
+        assert_eq!(node.len(), i + 1);
+        assert!(!node.is_empty());
+    }
+}
+
+#[test]
+fn test_depth_collinear() {
+    use crate::vector::DefaultVector;
+    let mut tree = unit_tree::<(), ()>(1);
+    assert_eq!(tree.root.depth(), 0);
+    tree.insert(DefaultVector([0.75, 0.75]), (), ()).unwrap();
+    assert_eq!(tree.root.depth(), 0);
+    // Each point halves the distance to the origin along the diagonal, so every
+    // insert has to split one level deeper than the previous one.
+    for k in 2..=20 {
+        let x = 0.75 / f64::from(1 << (k - 1));
+        tree.insert(DefaultVector([x, x]), (), ()).unwrap();
+        assert_eq!(tree.root.depth(), k - 1);
+    }
+}
+
+#[test]
+fn test_clear() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), ()>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    for p in random_points(30, 12) {
+        tree.insert(p, (), ()).unwrap();
+    }
+    tree.root.clear();
+    assert!(tree.root.is_empty());
+    assert_eq!(tree.root.iter().count(), 0);
+}
+
+#[test]
+fn test_count_in_area() {
+    use crate::vector::DefaultVector;
+    let (mut tree, points) = random_tree(300, 17, 2);
+    tree.root.assert_valid();
+    for area in [
+        ([0.0, 0.0], [1.0, 1.0]),
+        ([0.0, 0.0], [0.5, 0.5]),
+        ([0.1, 0.2], [0.7, 0.4]),
+        ([0.25, 0.25], [0.75, 0.75]),
+        ([2.0, 2.0], [3.0, 3.0]),
+    ] {
+        let area = Aabb::new(DefaultVector(area.0), DefaultVector(area.1));
+        assert_eq!(
+            tree.root.count_in_area(area),
+            tree.root.query_range(area).len()
+        );
+    }
+    for p in &points[..250] {
+        tree.remove(p).unwrap();
+        tree.root.assert_valid();
+    }
+    assert_eq!(tree.root.len(), 50);
+}
+
+#[test]
+fn test_extend() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let leaves = |points: &[DefaultVector<2>]| {
+        points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| DNode::Leaf {
+                area,
+                points: vec![(*p, (), i)],
+            })
+            .collect::<Vec<_>>()
+    };
+    let points = random_points(100, 23);
+
+    let mut sequential = DNode::<4, _, (), usize>::None;
+    for leaf in leaves(&points) {
+        sequential.insert(leaf).unwrap();
+    }
+    let mut extended = DNode::<4, _, (), usize>::None;
+    extended.extend(leaves(&points)).unwrap();
+    assert_eq!(extended.len(), 100);
+    assert_eq!(
+        extended.iter().map(|(_, _, i)| *i).collect::<Vec<_>>(),
+        sequential.iter().map(|(_, _, i)| *i).collect::<Vec<_>>()
+    );
+    extended.assert_valid();
+
+    // Leaves up to the rejected one stay inserted.
+    let mut partial = DNode::<4, _, (), usize>::None;
+    let mut items = leaves(&points[..3]);
+    items.insert(2, DNode::None);
+    assert_eq!(partial.extend(items), Err(InsertError::InsertedNone));
+    assert_eq!(partial.len(), 2);
+}
+
+#[test]
+fn test_structural_eq() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let build = |points: &[DefaultVector<2>]| {
+        let mut node = DNode::<4, _, (), usize>::None;
+        for (i, p) in points.iter().enumerate() {
+            node.insert(DNode::Leaf {
+                area,
+                points: vec![(*p, (), i)],
+            })
+            .unwrap();
+        }
+        node
+    };
+    let points = random_points(30, 59);
+    assert_eq!(build(&points), build(&points));
+    assert_eq!(build(&points).clone(), build(&points));
+    assert_ne!(build(&points), build(&points[..29]));
+    assert_ne!(build(&points), DNode::None);
+
+    let mut changed = build(&points);
+    *changed.iter_mut().next().unwrap().2 += 1;
+    assert_ne!(changed, build(&points));
+}
+
+#[test]
+fn test_retain() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let points = random_points(200, 61);
+    let mut tree = QuadTree::<4, _, (), usize>::with_bucket_size(area, 2);
+    let mut survivors = QuadTree::<4, _, (), usize>::with_bucket_size(area, 2);
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+        if p[0] < 0.3 && i % 3 == 0 {
+            survivors.insert(*p, (), i).unwrap();
+        }
+    }
+    tree.retain(|p, _, i| p[0] < 0.3 && i % 3 == 0);
+    assert_eq!(tree.len(), survivors.len());
+    tree.root.assert_valid();
+    assert_eq!(tree.root.depth(), survivors.root.depth());
+    let mut kept: Vec<_> = tree.iter().map(|(.., i)| *i).collect();
+    let mut expected: Vec<_> = survivors.iter().map(|(.., i)| *i).collect();
+    kept.sort();
+    expected.sort();
+    assert_eq!(kept, expected);
+
+    tree.retain(|_, _, i| *i == 0);
+    assert!(matches!(&tree.root, DNode::Leaf { points, .. } if points.len() == 1));
+    tree.retain(|_, _, _| false);
+    assert!(tree.is_empty());
+    assert!(tree.root.is_empty());
+
+    // Expiring entries by their metadata.
+    let mut tree = QuadTree::<4, _, u32, usize>::with_bucket_size(area, 2);
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, i as u32 % 10, i).unwrap();
+    }
+    tree.retain(|_, ttl, _| *ttl > 4);
+    assert_eq!(tree.len(), 100);
+    assert!(tree.iter().all(|(_, ttl, i)| *ttl > 4 && i % 10 > 4));
+    tree.root.assert_valid();
+}
+
+#[test]
+fn test_accessors() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut node = DNode::<4, _, char, u32>::None;
+    assert_eq!(node.area(), None);
+    assert_eq!(node.metadata(), None);
+    assert_eq!(node.data(), None);
+    node.insert(DNode::Leaf {
+        area,
+        points: vec![(DefaultVector([0.2, 0.2]), 'a', 1)],
+    })
+    .unwrap();
+    assert_eq!(node.area(), Some(area));
+    assert_eq!(node.metadata(), Some(&'a'));
+    assert_eq!(node.data(), Some(&1));
+}
+
+#[test]
+fn test_bounding_box() {
+    use crate::vector::DefaultVector;
+    let mut node = DNode::<4, _, (), ()>::None;
+    assert_eq!(node.bounding_box(), None);
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    for p in [[0.2, 0.7], [0.6, 0.3], [0.25, 0.4]] {
+        node.insert(DNode::Leaf {
+            area,
+            points: vec![(DefaultVector(p), (), ())],
+        })
+        .unwrap();
+    }
+    let bounding_box = node.bounding_box().unwrap();
+    assert_eq!(
+        (*bounding_box.min(), *bounding_box.max()),
+        ([0.2, 0.3], [0.6, 0.7])
+    );
+    assert_eq!(node.area(), Some(area));
+}
+
+#[test]
+fn test_centroid() {
+    use crate::QuadTree;
+    use crate::vector::{DefaultVector, IntVector};
+    let mut tree = QuadTree::<4, _, (), ()>::with_bucket_size(
+        (DefaultVector([0.0, 0.0]), DefaultVector([4.0, 4.0])),
+        1,
+    );
+    assert_eq!(tree.root.centroid(), None);
+    for p in [[1.0, 1.0], [3.0, 1.0], [0.5, 3.5], [1.5, 2.5]] {
+        tree.insert(DefaultVector(p), (), ()).unwrap();
+    }
+    assert_eq!(*tree.root.centroid().unwrap(), [1.5, 2.0]);
+
+    // Integer components round to the nearest mean rather than to zero, and
+    // sums past `i64::MAX` don't overflow.
+    let mut tree =
+        QuadTree::<4, _, (), ()>::new((IntVector([0, 0]), IntVector([i64::MAX, i64::MAX])));
+    for p in [[1, 2], [2, 4], [4, 4]] {
+        tree.insert(IntVector(p), (), ()).unwrap();
+    }
+    assert_eq!(tree.root.centroid(), Some(IntVector([2, 3])));
+    tree.clear();
+    tree.insert(IntVector([1 << 62, 6]), (), ()).unwrap();
+    tree.insert(IntVector([3 << 61, 8]), (), ()).unwrap();
+    assert_eq!(tree.root.centroid(), Some(IntVector([5 << 60, 7])));
+}
+
+#[test]
+fn test_validate() {
+    use crate::vector::DefaultVector;
+    let mut tree = unit_tree::<(), ()>(1);
+    assert_eq!(tree.root.validate(), Ok(()));
+    for p in random_points(100, 97) {
+        tree.insert(p, (), ()).unwrap();
+    }
+    assert_eq!(tree.root.validate(), Ok(()));
+    let invariant =
+        |node: &DNode<4, DefaultVector<2>, (), ()>| node.validate().map_err(|e| e.invariant);
+
+    let mut broken = tree.root.clone();
+    let DNode::Node { len, .. } = &mut broken else {
+        panic!("the root should have been split");
+    };
+    *len += 1;
+    assert_eq!(invariant(&broken), Err(InvalidTree::CountMismatch));
+
+    let mut broken = tree.root.clone();
+    let DNode::Node { children, .. } = &mut broken else {
+        unreachable!()
+    };
+    let swapped = std::mem::replace(&mut *children[0], DNode::None);
+    *children[0] = std::mem::replace(&mut *children[3], swapped);
+    let error = broken.validate().unwrap_err();
+    assert_eq!(
+        (error.invariant, error.path),
+        (InvalidTree::ChildOutsideCell, vec![0])
+    );
+
+    // Errors below the root point at the node they were found in.
+    let mut broken = tree.root.clone();
+    let (i, inner) = match &mut broken {
+        DNode::Node { children, .. } => children
+            .iter_mut()
+            .enumerate()
+            .find(|(_, c)| matches!(***c, DNode::Node { .. }))
+            .expect("100 points in unit buckets nest nodes"),
+        _ => unreachable!(),
+    };
+    if let DNode::Node { len, .. } = &mut **inner {
+        *len -= 1;
+    }
+    let error = broken.validate().unwrap_err();
+    assert_eq!(
+        error,
+        ValidationError {
+            invariant: InvalidTree::CountMismatch,
+            path: vec![i]
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        format!("cached point count is off at child path [{i}]")
+    );
+
+    let mut broken = DNode::<4, _, (), ()>::Leaf {
+        area: Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([0.5, 0.5])),
+        points: vec![(DefaultVector([0.7, 0.1]), (), ())],
+    };
+    assert_eq!(invariant(&broken), Err(InvalidTree::PointOutsideLeaf));
+    if let DNode::Leaf { points, .. } = &mut broken {
+        points.clear();
+    }
+    assert_eq!(invariant(&broken), Err(InvalidTree::EmptyLeaf));
+
+    let mut broken = tree.root.clone();
+    let DNode::Node { cells, .. } = &mut broken else {
+        unreachable!()
+    };
+    cells[3] = Aabb::new(cells[3].min(), DefaultVector([1.5, 1.0]));
+    assert_eq!(invariant(&broken), Err(InvalidTree::CellOutsideNode));
+
+    let mut broken = tree.root.clone();
+    let DNode::Node { cells, .. } = &mut broken else {
+        unreachable!()
+    };
+    cells[0] = Aabb::new(cells[0].min(), DefaultVector([0.75, 0.5]));
+    assert_eq!(invariant(&broken), Err(InvalidTree::OverlappingCells));
+
+    let mut broken = tree.root.clone();
+    let DNode::Node { len, children, .. } = &mut broken else {
+        unreachable!()
+    };
+    *len = 0;
+    for child in children {
+        **child = DNode::None;
+    }
+    assert_eq!(invariant(&broken), Err(InvalidTree::EmptyNode));
+    tree.root.assert_valid();
+}
+
+#[test]
+#[should_panic(expected = "invalid tree: node without children at the root")]
+fn test_assert_valid() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    DNode::<4, _, (), ()>::Node {
+        area,
+        metadata: (),
+        len: 0,
+        cells: std::array::from_fn(|i| area.split(i)),
+        children: std::array::from_fn(|_| Box::new(DNode::None)),
+    }
+    .assert_valid();
+}
+
+#[test]
+fn test_split_at_median() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut node = DNode::<4, _, (), usize>::Leaf {
+        area,
+        points: vec![(DefaultVector([0.05, 0.5]), (), 0)],
+    };
+    for (i, p) in random_points(19, 101).into_iter().enumerate() {
+        let position = DefaultVector([p[0] * 0.4, 0.45 + p[1] * 0.05]);
+        node.try_insert(position, (), i + 1).unwrap();
+    }
+    node.split_at_median::<EqualSplit, DefaultMetadata>(0);
+    assert_eq!(node.validate(), Ok(()));
+    assert_eq!(node.len(), 20);
+    let DNode::Node {
+        cells, children, ..
+    } = &node
+    else {
+        panic!("the points should have been split");
+    };
+    // Split in two along x, where the points spread furthest, y is left whole.
+    assert!(matches!(
+        (&*children[0], &*children[1]),
+        (DNode::None, DNode::None)
+    ));
+    assert_eq!((cells[2].min()[1], cells[2].max()[1]), (0.0, 1.0));
+    assert!(cells[2].max()[0] < 0.4 && cells[2].max()[0] == cells[3].min()[0]);
+    assert_eq!((children[2].len(), children[3].len()), (10, 10));
+    // Later points go to the halves too, the flat cells never take any.
+    node.try_insert(DefaultVector([0.9, 0.0]), (), 20).unwrap();
+    node.try_insert(DefaultVector([0.0, 0.0]), (), 21).unwrap();
+    assert_eq!((node[2].len(), node[3].len()), (11, 11));
+    node.assert_valid();
+
+    let mut stacked = DNode::<4, _, (), ()>::Leaf {
+        area,
+        points: vec![(DefaultVector([0.3, 0.3]), (), ()); 3],
+    };
+    let before = stacked.clone();
+    stacked.split_at_median::<EqualSplit, DefaultMetadata>(0);
+    assert_eq!(stacked, before);
 }
@@ -0,0 +1,99 @@
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display, Formatter};
+
+use super::DNode;
+use crate::vector::Vectorial;
+
+/// Indented outline in the style of `tree(1)`, one line per node or leaf
+/// giving its area, level below `self` and number of points, followed by the
+/// positions for leaves. Children are prefixed with their index, empty slots
+/// are left out.
+impl<const D: usize, T: Vectorial + Debug, U, V> Display for DNode<D, T, U, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_in(f, 0, "")
+    }
+}
+
+impl<const D: usize, T: Vectorial + Debug, U, V> DNode<D, T, U, V> {
+    /// Writes the line of `self` at `level`, then its children with `indent`
+    /// in front of their lines.
+    fn fmt_in(&self, f: &mut Formatter<'_>, level: usize, indent: &str) -> fmt::Result {
+        match self {
+            DNode::None => writeln!(f, "None"),
+            DNode::Leaf { area, points } => {
+                let (lo, hi) = (area.min(), area.max());
+                write!(f, "Leaf {lo:?} - {hi:?}, level {level}, ")?;
+                write!(f, "{} point{}:", points.len(), plural(points.len()))?;
+                for (i, (p, ..)) in points.iter().enumerate() {
+                    write!(f, "{} {p:?}", if i == 0 { "" } else { "," })?;
+                }
+                writeln!(f)
+            }
+            DNode::Node {
+                area,
+                len,
+                children,
+                ..
+            } => {
+                let (lo, hi) = (area.min(), area.max());
+                write!(f, "Node {lo:?} - {hi:?}, level {level}, ")?;
+                writeln!(f, "{len} point{}", plural(*len))?;
+                let used: Vec<_> = (0..D)
+                    .filter(|&i| !matches!(*children[i], DNode::None))
+                    .collect();
+                for (n, &i) in used.iter().enumerate() {
+                    let last = n + 1 == used.len();
+                    write!(f, "{indent}{}{i}: ", if last { "└── " } else { "├── " })?;
+                    let indent = format!("{indent}{}", if last { "    " } else { "│   " });
+                    children[i].fmt_in(f, level + 1, &indent)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 { "" } else { "s" }
+}
+
+#[test]
+fn test_display() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree = QuadTree::<4, _, (), ()>::with_bucket_size(
+        (DefaultVector([0.0, 0.0]), DefaultVector([4.0, 4.0])),
+        1,
+    );
+    assert_eq!(tree.root.to_string(), "None\n");
+    for p in [[1.0, 1.0], [3.0, 1.0], [0.5, 3.5], [1.5, 3.5]] {
+        tree.insert(DefaultVector(p), (), ()).unwrap();
+    }
+    let v = |x: f64, y: f64| format!("{:?}", DefaultVector([x, y]));
+    let expected = format!(
+        "Node {} - {}, level 0, 4 points\n\
+         ├── 0: Leaf {} - {}, level 1, 1 point: {}\n\
+         ├── 1: Leaf {} - {}, level 1, 1 point: {}\n\
+         └── 2: Node {} - {}, level 1, 2 points\n\
+         \x20   ├── 2: Leaf {} - {}, level 2, 1 point: {}\n\
+         \x20   └── 3: Leaf {} - {}, level 2, 1 point: {}\n",
+        v(0.0, 0.0),
+        v(4.0, 4.0),
+        v(0.0, 0.0),
+        v(2.0, 2.0),
+        v(1.0, 1.0),
+        v(2.0, 0.0),
+        v(4.0, 2.0),
+        v(3.0, 1.0),
+        v(0.0, 2.0),
+        v(2.0, 4.0),
+        v(0.0, 3.0),
+        v(1.0, 4.0),
+        v(0.5, 3.5),
+        v(1.0, 3.0),
+        v(2.0, 4.0),
+        v(1.5, 3.5),
+    );
+    assert_eq!(tree.root.to_string(), expected);
+}
@@ -0,0 +1,110 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Write};
+
+use super::DNode;
+use crate::vector::Vectorial;
+
+/// How [`DNode::write_dot`] renders a tree.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DotOptions {
+    /// Render empty child slots as small grey points instead of omitting them.
+    pub show_empty: bool,
+}
+
+impl<const D: usize, T: Vectorial + Debug, U, V> DNode<D, T, U, V> {
+    /// The tree as a Graphviz digraph, see [`DNode::write_dot`].
+    pub fn to_dot(&self, options: &DotOptions) -> String {
+        let mut out = String::new();
+        self.write_dot(&mut out, options)
+            .expect("writing to a String can't fail");
+        out
+    }
+
+    /// Writes the tree as a Graphviz digraph: nodes are labelled with their
+    /// area, leaves with the positions they hold, and edges with the index of
+    /// the child they lead to.
+    pub fn write_dot(&self, w: &mut impl Write, options: &DotOptions) -> fmt::Result {
+        writeln!(w, "digraph {{")?;
+        writeln!(w, "    node [shape=box];")?;
+        self.write_dot_in(w, options, &mut 0)?;
+        writeln!(w, "}}")
+    }
+
+    /// Writes `self` as node `*next` and its subtree after it.
+    fn write_dot_in(
+        &self,
+        w: &mut impl Write,
+        options: &DotOptions,
+        next: &mut usize,
+    ) -> fmt::Result {
+        let id = *next;
+        *next += 1;
+        match self {
+            DNode::None => writeln!(w, "    n{id} [shape=point, color=grey, label=\"\"];"),
+            DNode::Leaf { points, .. } => {
+                let label: Vec<_> = points.iter().map(|(p, ..)| escape(p)).collect();
+                writeln!(w, "    n{id} [label=\"{}\"];", label.join("\\n"))
+            }
+            DNode::Node { area, children, .. } => {
+                let (lo, hi) = (escape(&area.min()), escape(&area.max()));
+                writeln!(w, "    n{id} [style=rounded, label=\"{lo}\\n{hi}\"];")?;
+                for (i, child) in children.iter().enumerate() {
+                    if matches!(**child, DNode::None) && !options.show_empty {
+                        continue;
+                    }
+                    writeln!(w, "    n{id} -> n{} [label=\"{i}\"];", *next)?;
+                    child.write_dot_in(w, options, next)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Debug output of `value`, safe to put within a quoted DOT string.
+fn escape(value: &impl Debug) -> String {
+    format!("{value:?}")
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+}
+
+#[test]
+fn test_to_dot() {
+    use crate::vector::DefaultVector;
+    let (tree, _) = super::random_tree(50, 47, 2);
+    let count = |dot: &str, pattern: &str| dot.matches(pattern).count();
+
+    let dot = tree.root.to_dot(&DotOptions::default());
+    assert!(dot.starts_with("digraph {\n") && dot.ends_with("}\n"));
+    let (mut nodes, mut leaves, mut empty) = (0, 0, 0);
+    tree.root.visit(&mut |_, item| {
+        match item {
+            super::VisitItem::Node { .. } => nodes += 1,
+            super::VisitItem::Leaf { points } => leaves += points.len(),
+        }
+        super::VisitAction::Continue
+    });
+    count_empty(&tree.root, &mut empty);
+    // Each leaf labels one line per point.
+    assert_eq!(count(&dot, "DefaultVector") - 2 * nodes, leaves);
+    assert_eq!(count(&dot, "style=rounded"), nodes);
+    assert_eq!(count(&dot, "shape=point"), 0);
+
+    let dot = tree.root.to_dot(&DotOptions { show_empty: true });
+    assert_eq!(count(&dot, "shape=point"), empty);
+    // A tree of `nodes` interior nodes has `4 * nodes` child slots.
+    assert_eq!(count(&dot, " -> "), 4 * nodes);
+
+    fn count_empty(node: &DNode<4, DefaultVector<2>, (), usize>, empty: &mut usize) {
+        if let DNode::Node { children, .. } = node {
+            for child in children {
+                match **child {
+                    DNode::None => *empty += 1,
+                    _ => count_empty(child, empty),
+                }
+            }
+        }
+    }
+}
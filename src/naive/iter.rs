@@ -0,0 +1,430 @@
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use super::DNode;
+use crate::aabb::Aabb;
+use crate::vector::Vectorial;
+
+/// Depth-first iterator over the points stored in a tree, see [`DNode::iter`].
+pub struct LeafIter<'a, const D: usize, T: Vectorial, U, V> {
+    stack: Vec<&'a DNode<D, T, U, V>>,
+    points: core::slice::Iter<'a, (T, U, V)>,
+}
+
+impl<'a, const D: usize, T: Vectorial, U, V> Iterator for LeafIter<'a, D, T, U, V> {
+    type Item = (&'a T, &'a U, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((position, metadata, data)) = self.points.next() {
+                return Some((position, metadata, data));
+            }
+            match self.stack.pop()? {
+                DNode::None => {}
+                DNode::Leaf { points, .. } => self.points = points.iter(),
+                DNode::Node { children, .. } => {
+                    self.stack.extend(children.iter().rev().map(|c| &**c));
+                }
+            }
+        }
+    }
+}
+
+// Once the stack runs dry it stays empty.
+impl<const D: usize, T: Vectorial, U, V> FusedIterator for LeafIter<'_, D, T, U, V> {}
+
+/// Depth-first iterator over the stored points along with the number of
+/// interior nodes above their leaf, see [`DNode::iter_with_depth`].
+pub struct DepthIter<'a, const D: usize, T: Vectorial, U, V> {
+    stack: Vec<(usize, &'a DNode<D, T, U, V>)>,
+    depth: usize,
+    points: core::slice::Iter<'a, (T, U, V)>,
+}
+
+impl<'a, const D: usize, T: Vectorial, U, V> Iterator for DepthIter<'a, D, T, U, V> {
+    type Item = (usize, &'a T, &'a U, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((position, metadata, data)) = self.points.next() {
+                return Some((self.depth, position, metadata, data));
+            }
+            match self.stack.pop()? {
+                (_, DNode::None) => {}
+                (depth, DNode::Leaf { points, .. }) => {
+                    self.depth = depth;
+                    self.points = points.iter();
+                }
+                (depth, DNode::Node { children, .. }) => {
+                    self.stack
+                        .extend(children.iter().rev().map(|c| (depth + 1, &**c)));
+                }
+            }
+        }
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V> FusedIterator for DepthIter<'_, D, T, U, V> {}
+
+/// Depth-first iterator handing out mutable payloads, see [`DNode::iter_mut`].
+/// Positions stay shared so the structure of the tree can't be broken.
+pub struct LeafIterMut<'a, const D: usize, T: Vectorial, U, V> {
+    stack: Vec<&'a mut DNode<D, T, U, V>>,
+    points: core::slice::IterMut<'a, (T, U, V)>,
+}
+
+impl<'a, const D: usize, T: Vectorial, U, V> Iterator for LeafIterMut<'a, D, T, U, V> {
+    type Item = (&'a T, &'a mut U, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((position, metadata, data)) = self.points.next() {
+                return Some((position, metadata, data));
+            }
+            match self.stack.pop()? {
+                DNode::None => {}
+                DNode::Leaf { points, .. } => self.points = points.iter_mut(),
+                DNode::Node { children, .. } => {
+                    self.stack
+                        .extend(children.iter_mut().rev().map(|c| &mut **c));
+                }
+            }
+        }
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V> FusedIterator for LeafIterMut<'_, D, T, U, V> {}
+
+/// Breadth-first iterator over nodes and points, see [`DNode::iter_levels`].
+pub struct LevelIter<'a, const D: usize, T: Vectorial, U, V> {
+    queue: VecDeque<(usize, &'a DNode<D, T, U, V>)>,
+    /// Depth and area of the leaf whose remaining `points` are yielded first.
+    leaf: Option<(usize, &'a Aabb<T>)>,
+    points: core::slice::Iter<'a, (T, U, V)>,
+}
+
+impl<'a, const D: usize, T: Vectorial, U, V> Iterator for LevelIter<'a, D, T, U, V> {
+    type Item = (usize, &'a Aabb<T>, Option<&'a T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let (Some((depth, area)), Some((position, ..))) = (self.leaf, self.points.next()) {
+            return Some((depth, area, Some(position)));
+        }
+        loop {
+            let (depth, node) = self.queue.pop_front()?;
+            match node {
+                DNode::None => {}
+                DNode::Leaf { area, points } => {
+                    self.points = points.iter();
+                    self.leaf = Some((depth, area));
+                    let first = self.points.next().map(|(position, ..)| position);
+                    return Some((depth, area, first));
+                }
+                DNode::Node { area, children, .. } => {
+                    self.queue
+                        .extend(children.iter().map(|c| (depth + 1, &**c)));
+                    return Some((depth, area, None));
+                }
+            }
+        }
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V> FusedIterator for LevelIter<'_, D, T, U, V> {}
+
+/// Consuming iterator moving the stored points out of a tree, see
+/// [`DNode::into_iter`]. Nodes are taken apart one level at a time, so
+/// consuming or dropping it never recurses however deep the tree is.
+pub struct IntoIter<const D: usize, T: Vectorial, U, V> {
+    stack: Vec<DNode<D, T, U, V>>,
+    points: alloc::vec::IntoIter<(T, U, V)>,
+}
+
+impl<const D: usize, T: Vectorial, U, V> IntoIter<D, T, U, V> {
+    /// Pushes the children of `node`, handing back its points if it is a leaf.
+    fn dismantle(&mut self, node: DNode<D, T, U, V>) -> Option<Vec<(T, U, V)>> {
+        match node {
+            DNode::None => None,
+            DNode::Leaf { points, .. } => Some(points),
+            DNode::Node { children, .. } => {
+                self.stack.extend(children.into_iter().rev().map(|c| *c));
+                None
+            }
+        }
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V> Iterator for IntoIter<D, T, U, V> {
+    type Item = (T, U, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(point) = self.points.next() {
+                return Some(point);
+            }
+            let node = self.stack.pop()?;
+            if let Some(points) = self.dismantle(node) {
+                self.points = points.into_iter();
+            }
+        }
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V> FusedIterator for IntoIter<D, T, U, V> {}
+
+impl<const D: usize, T: Vectorial, U, V> Drop for IntoIter<D, T, U, V> {
+    fn drop(&mut self) {
+        while let Some(node) = self.stack.pop() {
+            self.dismantle(node);
+        }
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V> DNode<D, T, U, V> {
+    /// Iterates over every stored point, children visited in index order.
+    pub fn iter(&self) -> LeafIter<'_, D, T, U, V> {
+        LeafIter {
+            stack: vec![self],
+            points: Default::default(),
+        }
+    }
+
+    /// Like [`DNode::iter`], also yielding how many interior nodes sit above
+    /// the leaf holding each point.
+    pub fn iter_with_depth(&self) -> DepthIter<'_, D, T, U, V> {
+        DepthIter {
+            stack: vec![(0, self)],
+            depth: 0,
+            points: Default::default(),
+        }
+    }
+
+    /// Walks the tree level by level, yielding how deep each node sits along
+    /// with its area. Interior nodes come without a position, leaves once per
+    /// stored point. Empty slots are skipped.
+    pub fn iter_levels(&self) -> LevelIter<'_, D, T, U, V> {
+        LevelIter {
+            queue: VecDeque::from([(0, self)]),
+            leaf: None,
+            points: Default::default(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> LeafIterMut<'_, D, T, U, V> {
+        LeafIterMut {
+            stack: vec![self],
+            points: Default::default(),
+        }
+    }
+
+    /// Moves every stored point out, leaving `DNode::None` behind right away,
+    /// so the tree stays empty however much of the iterator is consumed.
+    pub fn drain(&mut self) -> IntoIter<D, T, U, V> {
+        core::mem::replace(self, DNode::None).into_iter()
+    }
+}
+
+impl<'a, const D: usize, T: Vectorial, U, V> IntoIterator for &'a DNode<D, T, U, V> {
+    type Item = (&'a T, &'a U, &'a V);
+    type IntoIter = LeafIter<'a, D, T, U, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, const D: usize, T: Vectorial, U, V> IntoIterator for &'a mut DNode<D, T, U, V> {
+    type Item = (&'a T, &'a mut U, &'a mut V);
+    type IntoIter = LeafIterMut<'a, D, T, U, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V> IntoIterator for DNode<D, T, U, V> {
+    type Item = (T, U, V);
+    type IntoIter = IntoIter<D, T, U, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            stack: vec![self],
+            points: Vec::new().into_iter(),
+        }
+    }
+}
+
+#[test]
+fn test_leaf_iter() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, u32, usize>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    for (i, p) in super::random_points(100, 4).into_iter().enumerate() {
+        tree.insert(p, 0, i).unwrap();
+    }
+    let mut seen: Vec<usize> = tree.root.iter().map(|(_, _, i)| *i).collect();
+    seen.sort();
+    assert_eq!(seen, (0..100).collect::<Vec<_>>());
+
+    for (_, metadata, data) in &mut tree.root {
+        *metadata += 1;
+        *data *= 2;
+    }
+    assert!(
+        (&tree.root)
+            .into_iter()
+            .all(|(_, m, d)| *m == 1 && d % 2 == 0)
+    );
+    assert_eq!(DNode::<4, DefaultVector<2>, (), ()>::None.iter().count(), 0);
+
+    let mut iter = tree.root.iter();
+    assert_eq!(iter.by_ref().count(), 100);
+    assert!(iter.next().is_none());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_iter_levels() {
+    use crate::vector::DefaultVector;
+    let mut tree = super::unit_tree::<(), ()>(1);
+    for p in [[0.1, 0.1], [0.2, 0.2], [0.9, 0.9]] {
+        tree.insert(DefaultVector(p), (), ()).unwrap();
+    }
+    let levels: Vec<_> = tree
+        .root
+        .iter_levels()
+        .map(|(depth, area, position)| (depth, *area.min(), *area.max(), position.map(|p| **p)))
+        .collect();
+    assert_eq!(
+        levels,
+        [
+            (0, [0.0, 0.0], [1.0, 1.0], None),
+            (1, [0.0, 0.0], [0.5, 0.5], None),
+            (1, [0.5, 0.5], [1.0, 1.0], Some([0.9, 0.9])),
+            (2, [0.0, 0.0], [0.25, 0.25], None),
+            (3, [0.0, 0.0], [0.125, 0.125], Some([0.1, 0.1])),
+            (3, [0.125, 0.125], [0.25, 0.25], Some([0.2, 0.2])),
+        ]
+    );
+    let depths: Vec<_> = tree.root.iter_levels().map(|(depth, ..)| depth).collect();
+    assert!(depths.is_sorted());
+    assert_eq!(
+        DNode::<4, DefaultVector<2>, (), ()>::None
+            .iter_levels()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn test_into_iter() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree = QuadTree::<4, _, (), Vec<usize>>::new((
+        DefaultVector([0.0, 0.0]),
+        DefaultVector([1.0, 1.0]),
+    ));
+    for (i, p) in super::random_points(100, 8).into_iter().enumerate() {
+        tree.insert(p, (), vec![i]).unwrap();
+    }
+    let mut seen: Vec<usize> = tree.into_iter().flat_map(|(_, _, data)| data).collect();
+    seen.sort();
+    assert_eq!(seen, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_drain() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut node = DNode::<4, _, (), usize>::None;
+    for (i, p) in super::random_points(50, 9).into_iter().enumerate() {
+        node.insert(DNode::Leaf {
+            area,
+            points: vec![(p, (), i)],
+        })
+        .unwrap();
+    }
+    let mut drain = node.drain();
+    assert_eq!(drain.by_ref().take(10).count(), 10);
+    drop(drain);
+    assert!(node.is_empty());
+    assert_eq!(node.len(), 0);
+    assert_eq!(node.drain().count(), 0);
+}
+
+#[test]
+fn test_into_iter_deep() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let chain = |depth| {
+        let mut node = DNode::<4, _, (), ()>::Leaf {
+            area,
+            points: vec![(DefaultVector([0.0, 0.0]), (), ())],
+        };
+        for _ in 0..depth {
+            let mut children: [Box<DNode<4, _, (), ()>>; 4] =
+                std::array::from_fn(|_| Box::new(DNode::None));
+            *children[0] = node;
+            node = DNode::Node {
+                area,
+                metadata: (),
+                len: 1,
+                cells: [area; 4],
+                children,
+            };
+        }
+        node
+    };
+    let mut iter = chain(100_000).into_iter();
+    assert!(iter.next().is_some());
+    assert!(iter.next().is_none());
+    // Dropping an iterator that hasn't got to the bottom doesn't recurse either.
+    drop(chain(100_000).into_iter());
+}
+
+#[test]
+fn test_iter_with_depth() {
+    use crate::vector::DefaultVector;
+    let mut tree = super::unit_tree::<(), usize>(2);
+    tree.insert(DefaultVector([0.5, 0.5]), (), 0).unwrap();
+    assert_eq!(
+        tree.root
+            .iter_with_depth()
+            .map(|(d, ..)| d)
+            .collect::<Vec<_>>(),
+        [0]
+    );
+    for (i, p) in super::random_points(60, 12).into_iter().enumerate() {
+        tree.insert(p, (), i + 1).unwrap();
+    }
+    let with_depth: Vec<_> = tree
+        .root
+        .iter_with_depth()
+        .map(|(d, p, _, i)| (p, *i, d))
+        .collect();
+    let plain: Vec<_> = tree.root.iter().map(|(p, _, i)| (p, *i)).collect();
+    assert_eq!(
+        with_depth
+            .iter()
+            .map(|(p, i, _)| (*p, *i))
+            .collect::<Vec<_>>(),
+        plain
+    );
+    let mut by_levels: Vec<_> = tree
+        .root
+        .iter_levels()
+        .filter_map(|(d, _, p)| p.map(|p| (p, d)))
+        .collect();
+    let mut by_depth: Vec<_> = with_depth.iter().map(|(p, _, d)| (*p, *d)).collect();
+    let key = |(p, _): &(&DefaultVector<2>, usize)| (p[0].to_bits(), p[1].to_bits());
+    by_levels.sort_by_key(key);
+    by_depth.sort_by_key(key);
+    assert_eq!(by_depth, by_levels);
+    assert_eq!(
+        by_depth.iter().map(|(_, d)| *d).max(),
+        Some(tree.root.depth())
+    );
+}
@@ -0,0 +1,256 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::DNode;
+use crate::aabb::Aabb;
+use crate::scalar::Scalar;
+use crate::vector::Vectorial;
+
+/// Segment `origin + direction * t` for `t` in `0..=max_t`, with the first
+/// `axes` components of its vectors taken as `f64`.
+#[derive(Clone, Copy)]
+pub(crate) struct Ray<'a, T> {
+    pub(crate) origin: &'a T,
+    pub(crate) direction: &'a T,
+    pub(crate) max_t: f64,
+    pub(crate) axes: usize,
+}
+
+/// Whether a segment query finds nothing up front. Negative bounds would
+/// shrink the areas searched while a squared `radius` still lets points
+/// through, and a `NaN` one compares false against everything.
+pub(crate) fn rejects(max_t: f64, radius: f64) -> bool {
+    max_t.is_nan() || max_t < 0.0 || radius.is_nan() || radius < 0.0
+}
+
+impl<T: Vectorial> Ray<'_, T> {
+    /// Smallest `t` at which the segment is inside `area` grown by `margin`
+    /// on every side, `None` when it misses. Slab method: the `t` range within
+    /// each axis' bounds is intersected, axes the ray runs parallel to either
+    /// keep it whole or rule it out without dividing by zero.
+    pub(crate) fn entry(&self, area: Aabb<T>, margin: f64) -> Option<f64> {
+        let (mut near, mut far) = (0.0, self.max_t);
+        for k in 0..self.axes {
+            let o = self.origin.component(k).to_f64();
+            let d = self.direction.component(k).to_f64();
+            let lo = area.min().component(k).to_f64() - margin;
+            let hi = area.max().component(k).to_f64() + margin;
+            if d == 0.0 {
+                if o < lo || o > hi {
+                    return None;
+                }
+                continue;
+            }
+            let (a, b) = ((lo - o) / d, (hi - o) / d);
+            near = f64::max(near, a.min(b));
+            far = f64::min(far, a.max(b));
+        }
+        (near <= far).then_some(near)
+    }
+
+    /// Parameter of the point of the segment closest to `position` and the
+    /// squared distance between both.
+    pub(crate) fn closest(&self, position: &T) -> (f64, f64) {
+        let along = |v: &T, k: usize| v.component(k).to_f64();
+        let (mut dot, mut length) = (0.0, 0.0);
+        for k in 0..self.axes {
+            let d = along(self.direction, k);
+            dot += (along(position, k) - along(self.origin, k)) * d;
+            length += d * d;
+        }
+        let t = match length {
+            0.0 => 0.0,
+            _ => (dot / length).clamp(0.0, self.max_t),
+        };
+        let dist = (0..self.axes)
+            .map(|k| {
+                let on_ray = along(self.origin, k) + along(self.direction, k) * t;
+                let offset = along(position, k) - on_ray;
+                offset * offset
+            })
+            .sum();
+        (t, dist)
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V> DNode<D, T, U, V> {
+    /// Every stored point at most `radius` away from the segment
+    /// `origin + direction * t` with `t` in `0..=max_t`, along with the `t`
+    /// closest to it, sorted by that. Subtrees are skipped by testing the
+    /// segment against their area grown by `radius`. Finds nothing when
+    /// `max_t` or `radius` is negative or `NaN`.
+    pub fn query_ray(
+        &self,
+        origin: &T,
+        direction: &T,
+        max_t: f64,
+        radius: f64,
+    ) -> Vec<(&T, &U, &V, f64)> {
+        if rejects(max_t, radius) {
+            return Vec::new();
+        }
+        let ray = self.ray(origin, direction, max_t);
+        let mut found = Vec::new();
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            match node {
+                DNode::None => {}
+                DNode::Leaf { points, .. } => {
+                    for (position, metadata, data) in points {
+                        let (t, dist) = ray.closest(position);
+                        if dist <= radius * radius {
+                            found.push((position, metadata, data, t));
+                        }
+                    }
+                }
+                DNode::Node { area, children, .. } => {
+                    if ray.entry(*area, radius).is_some() {
+                        stack.extend(children.iter().map(|c| &**c));
+                    }
+                }
+            }
+        }
+        found.sort_by(|a, b| a.3.total_cmp(&b.3));
+        found
+    }
+
+    /// The first point [`DNode::query_ray`] would return. Children are
+    /// searched in the order the segment enters them, and no further once it
+    /// enters the next one past the best `t` found so far.
+    pub fn query_ray_first(
+        &self,
+        origin: &T,
+        direction: &T,
+        max_t: f64,
+        radius: f64,
+    ) -> Option<(&T, &U, &V, f64)> {
+        if rejects(max_t, radius) {
+            return None;
+        }
+        let ray = self.ray(origin, direction, max_t);
+        let mut best = None;
+        self.ray_first_in(&ray, radius, &mut best);
+        best
+    }
+
+    fn ray<'a>(&self, origin: &'a T, direction: &'a T, max_t: f64) -> Ray<'a, T> {
+        Ray {
+            origin,
+            direction,
+            max_t,
+            axes: D.trailing_zeros() as usize,
+        }
+    }
+
+    fn ray_first_in<'a>(
+        &'a self,
+        ray: &Ray<'_, T>,
+        radius: f64,
+        best: &mut Option<(&'a T, &'a U, &'a V, f64)>,
+    ) {
+        let best_t = |best: &Option<(_, _, _, f64)>| best.map_or(f64::INFINITY, |b| b.3);
+        match self {
+            DNode::None => {}
+            DNode::Leaf { points, .. } => {
+                for (position, metadata, data) in points {
+                    let (t, dist) = ray.closest(position);
+                    if dist <= radius * radius && t < best_t(best) {
+                        *best = Some((position, metadata, data, t));
+                    }
+                }
+            }
+            DNode::Node { children, .. } => {
+                // A point's `t` is never below where the segment enters the
+                // grown area holding it.
+                let mut order: [_; D] = core::array::from_fn(|i| {
+                    let entry = children[i].area().and_then(|a| ray.entry(a, radius));
+                    (entry.unwrap_or(f64::INFINITY), i)
+                });
+                order.sort_by(|a, b| a.0.total_cmp(&b.0));
+                for (entry, i) in order {
+                    if entry == f64::INFINITY || entry > best_t(best) {
+                        break;
+                    }
+                    children[i].ray_first_in(ray, radius, best);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_query_ray() {
+    use crate::vector::DefaultVector;
+    let (tree, points) = super::random_tree(300, 31, 2);
+    let brute = |origin: DefaultVector<2>, direction: DefaultVector<2>, max_t, radius| {
+        let ray = tree.root.ray(&origin, &direction, max_t);
+        let mut hits: Vec<_> = points
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| {
+                let (t, dist) = ray.closest(p);
+                (dist <= radius * radius).then_some((t, i))
+            })
+            .collect();
+        hits.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        hits
+    };
+    for (origin, direction, max_t, radius) in [
+        ([-0.5, 0.1], [1.0, 0.7], 2.0, 0.05),
+        ([0.9, 0.9], [-0.3, -0.2], 3.0, 0.02),
+        // Parallel to the y axis, and a segment shrunk to a single point.
+        ([0.5, 2.0], [0.0, -1.0], 10.0, 0.03),
+        ([0.3, 0.3], [0.0, 0.0], 1.0, 0.1),
+        ([2.0, 2.0], [1.0, 0.0], 5.0, 0.1),
+    ] {
+        let (origin, direction) = (DefaultVector(origin), DefaultVector(direction));
+        let found: Vec<_> = tree
+            .root
+            .query_ray(&origin, &direction, max_t, radius)
+            .into_iter()
+            .map(|(_, _, i, t)| (t, *i))
+            .collect();
+        let expected = brute(origin, direction, max_t, radius);
+        // Points at the same `t` may come in any order.
+        let mut sorted = found.clone();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        assert_eq!(sorted, expected);
+        assert!(found.is_sorted_by(|a, b| a.0 <= b.0));
+        let first = tree
+            .root
+            .query_ray_first(&origin, &direction, max_t, radius)
+            .map(|(_, _, i, t)| (t, *i));
+        match first {
+            Some(first) => assert!(first.0 == expected[0].0 && expected.contains(&first)),
+            None => assert!(expected.is_empty()),
+        }
+    }
+    let (origin, direction) = (DefaultVector([-0.5, 0.1]), DefaultVector([1.0, 0.7]));
+    for (max_t, radius) in [
+        (2.0, -0.05),
+        (2.0, f64::NAN),
+        (-1.0, 0.05),
+        (f64::NAN, 0.05),
+    ] {
+        assert!(
+            tree.root
+                .query_ray(&origin, &direction, max_t, radius)
+                .is_empty()
+        );
+        assert!(
+            tree.root
+                .query_ray_first(&origin, &direction, max_t, radius)
+                .is_none()
+        );
+    }
+    assert!(
+        tree.root
+            .query_ray_first(
+                &DefaultVector([2.0, 2.0]),
+                &DefaultVector([1.0, 0.0]),
+                5.0,
+                0.1
+            )
+            .is_none()
+    );
+}
@@ -0,0 +1,170 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use super::DNode;
+use crate::vector::Vectorial;
+
+/// Shape of a tree, see [`DNode::stats`]. Levels count from the root at 0.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TreeStats {
+    pub points: usize,
+    /// Interior nodes.
+    pub nodes: usize,
+    pub leaves: usize,
+    /// Child slots of interior nodes holding `DNode::None`.
+    pub empty_slots: usize,
+    /// Level of the deepest leaf, same as [`DNode::depth`].
+    pub max_depth: usize,
+    /// Mean level of the leaves, `0.0` without any.
+    pub average_leaf_depth: f64,
+    /// Number of leaves on each level, down to `max_depth`.
+    pub leaves_per_level: Vec<usize>,
+}
+
+impl<const D: usize, T: Vectorial, U, V> DNode<D, T, U, V> {
+    /// Counts nodes, leaves and empty slots in one walk over the tree, to tune
+    /// bucket sizes and depth limits by.
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats::default();
+        let mut level_sum = 0;
+        let mut stack = vec![(0, self)];
+        while let Some((level, node)) = stack.pop() {
+            match node {
+                DNode::None => {}
+                DNode::Leaf { points, .. } => {
+                    stats.points += points.len();
+                    stats.leaves += 1;
+                    stats.max_depth = stats.max_depth.max(level);
+                    level_sum += level;
+                    if stats.leaves_per_level.len() <= level {
+                        stats.leaves_per_level.resize(level + 1, 0);
+                    }
+                    stats.leaves_per_level[level] += 1;
+                }
+                DNode::Node { children, .. } => {
+                    stats.nodes += 1;
+                    for child in children {
+                        match **child {
+                            DNode::None => stats.empty_slots += 1,
+                            _ => stack.push((level + 1, child)),
+                        }
+                    }
+                }
+            }
+        }
+        if stats.leaves > 0 {
+            stats.average_leaf_depth = level_sum as f64 / stats.leaves as f64;
+        }
+        stats
+    }
+
+    /// Alias of [`DNode::stats`].
+    pub fn stat(&self) -> TreeStats {
+        self.stats()
+    }
+}
+
+/// Totals first, then one row per level of leaves.
+impl Display for TreeStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "points             {}", self.points)?;
+        writeln!(f, "nodes              {}", self.nodes)?;
+        writeln!(f, "leaves             {}", self.leaves)?;
+        writeln!(f, "empty slots        {}", self.empty_slots)?;
+        writeln!(f, "max depth          {}", self.max_depth)?;
+        writeln!(f, "average leaf depth {:.2}", self.average_leaf_depth)?;
+        writeln!(f, "level  leaves")?;
+        for (level, leaves) in self.leaves_per_level.iter().enumerate() {
+            writeln!(f, "{level:>5}  {leaves:>6}")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_stats() {
+    use crate::QuadTree;
+    use crate::vector::DefaultVector;
+    let mut tree = QuadTree::<4, _, (), ()>::with_bucket_size(
+        (DefaultVector([0.0, 0.0]), DefaultVector([4.0, 4.0])),
+        1,
+    );
+    assert_eq!(tree.root.stats(), TreeStats::default());
+    for p in [[1.0, 1.0], [3.0, 1.0], [0.5, 3.5], [1.5, 3.5]] {
+        tree.insert(DefaultVector(p), (), ()).unwrap();
+    }
+    let stats = tree.root.stats();
+    assert_eq!(
+        stats,
+        TreeStats {
+            points: 4,
+            nodes: 2,
+            leaves: 4,
+            empty_slots: 3,
+            max_depth: 2,
+            average_leaf_depth: 1.5,
+            leaves_per_level: vec![0, 2, 2],
+        }
+    );
+    assert_eq!(stats.max_depth, tree.root.depth());
+    assert_eq!(
+        (tree.root.stat(), tree.stat()),
+        (stats.clone(), stats.clone())
+    );
+    assert_eq!(
+        stats.to_string(),
+        "points             4\n\
+         nodes              2\n\
+         leaves             4\n\
+         empty slots        3\n\
+         max depth          2\n\
+         average leaf depth 1.50\n\
+         level  leaves\n\
+         \x20   0       0\n\
+         \x20   1       2\n\
+         \x20   2       2\n"
+    );
+}
+
+#[test]
+fn test_stats_by_hand() {
+    use crate::aabb::Aabb;
+    use crate::vector::DefaultVector;
+    let area = |lo: f64, hi: f64| Aabb::new(DefaultVector([lo]), DefaultVector([hi]));
+    let leaf = |lo, hi, xs: &[f64]| DNode::Leaf {
+        area: area(lo, hi),
+        points: xs.iter().map(|x| (DefaultVector([*x]), (), ())).collect(),
+    };
+    let tree: DNode<2, _, (), ()> = DNode::Node {
+        area: area(0.0, 4.0),
+        metadata: (),
+        len: 3,
+        cells: [area(0.0, 2.0), area(2.0, 4.0)],
+        children: [
+            Box::new(leaf(0.0, 2.0, &[0.5, 1.5])),
+            Box::new(DNode::Node {
+                area: area(2.0, 4.0),
+                metadata: (),
+                len: 1,
+                cells: [area(2.0, 3.0), area(3.0, 4.0)],
+                children: [Box::new(DNode::None), Box::new(leaf(3.0, 4.0, &[3.5]))],
+            }),
+        ],
+    };
+    tree.assert_valid();
+    let stats = tree.stats();
+    assert_eq!(
+        stats,
+        TreeStats {
+            points: 3,
+            nodes: 2,
+            leaves: 2,
+            empty_slots: 1,
+            max_depth: 2,
+            average_leaf_depth: 1.5,
+            leaves_per_level: vec![0, 1, 1],
+        }
+    );
+    assert_eq!((stats.points, stats.max_depth), (tree.len(), tree.depth()));
+}
@@ -0,0 +1,123 @@
+use alloc::string::String;
+use alloc::string::ToString;
+use core::fmt::Write;
+
+use super::{DNode, VisitAction, VisitItem};
+use crate::vector::DefaultVector;
+
+/// How [`DNode::to_svg`] draws a tree. Sizes are fractions of the larger side
+/// of the root area, so the defaults suit trees of any extent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgOptions {
+    pub stroke_width: f64,
+    pub point_radius: f64,
+    /// Outline the areas of interior nodes too, not just those of leaves.
+    pub draw_nodes: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            stroke_width: 0.002,
+            point_radius: 0.005,
+            draw_nodes: true,
+        }
+    }
+}
+
+impl<const D: usize, U, V> DNode<D, DefaultVector<2>, U, V> {
+    /// Standalone SVG document drawing the areas of the tree as outlines and
+    /// every stored point as a dot, y pointing up. An empty tree has no area
+    /// to derive the view from and yields an empty document.
+    pub fn to_svg(&self, options: SvgOptions) -> String {
+        let Some(root) = self.area() else {
+            return "<svg xmlns=\"http://www.w3.org/2000/svg\"/>\n".to_string();
+        };
+        let (lo, hi) = (root.min(), root.max());
+        let scale = (hi[0] - lo[0]).max(hi[1] - lo[1]);
+        let (stroke, radius) = (options.stroke_width * scale, options.point_radius * scale);
+
+        let mut out = String::new();
+        // Writing to a String can't fail, hence the ignored results.
+        let _ = writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+            lo[0] - stroke,
+            -hi[1] - stroke,
+            hi[0] - lo[0] + 2.0 * stroke,
+            hi[1] - lo[1] + 2.0 * stroke,
+        );
+        let _ = writeln!(
+            out,
+            "<g transform=\"scale(1 -1)\" fill=\"none\" stroke=\"black\" stroke-width=\"{stroke}\">"
+        );
+        self.visit(&mut |area, item| {
+            let (a, b) = (area.min(), area.max());
+            let outline = |out: &mut String| {
+                let _ = writeln!(
+                    out,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                    a[0],
+                    a[1],
+                    b[0] - a[0],
+                    b[1] - a[1]
+                );
+            };
+            match item {
+                VisitItem::Node { .. } if options.draw_nodes => outline(&mut out),
+                VisitItem::Node { .. } => {}
+                VisitItem::Leaf { points } => {
+                    outline(&mut out);
+                    for (p, ..) in points {
+                        let _ = writeln!(
+                            out,
+                            "<circle cx=\"{}\" cy=\"{}\" r=\"{radius}\" fill=\"black\" stroke=\"none\"/>",
+                            p[0], p[1]
+                        );
+                    }
+                }
+            }
+            VisitAction::Continue
+        });
+        out.push_str("</g>\n</svg>\n");
+        out
+    }
+}
+
+#[test]
+fn test_to_svg() {
+    use crate::QuadTree;
+    let mut tree = QuadTree::<4, _, (), ()>::with_bucket_size(
+        (DefaultVector([0.0, 0.0]), DefaultVector([2.0, 1.0])),
+        2,
+    );
+    assert_eq!(
+        tree.root.to_svg(SvgOptions::default()),
+        "<svg xmlns=\"http://www.w3.org/2000/svg\"/>\n"
+    );
+    for p in super::random_points(50, 59) {
+        tree.insert(p, (), ()).unwrap();
+    }
+    let (mut nodes, mut leaves) = (0, 0);
+    tree.root.visit(&mut |_, item| {
+        match item {
+            VisitItem::Node { .. } => nodes += 1,
+            VisitItem::Leaf { .. } => leaves += 1,
+        }
+        VisitAction::Continue
+    });
+
+    let svg = tree.root.to_svg(SvgOptions::default());
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\""));
+    assert!(svg.ends_with("</g>\n</svg>\n"));
+    assert_eq!(svg.matches("<circle ").count(), 50);
+    assert_eq!(svg.matches("<rect ").count(), nodes + leaves);
+    // Sizes scale with the wider side of the root area.
+    assert!(svg.contains("stroke-width=\"0.004\""));
+
+    let svg = tree.root.to_svg(SvgOptions {
+        draw_nodes: false,
+        ..SvgOptions::default()
+    });
+    assert_eq!(svg.matches("<rect ").count(), leaves);
+}
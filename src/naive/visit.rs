@@ -0,0 +1,107 @@
+use super::DNode;
+use crate::aabb::Aabb;
+use crate::vector::Vectorial;
+
+/// What [`DNode::visit`] hands to its callback along with the area.
+#[derive(Clone, Copy, Debug)]
+pub enum VisitItem<'a, T, U, V> {
+    /// An interior node, visited before any of its children.
+    Node {
+        metadata: &'a U,
+        /// Number of points stored below the node.
+        len: usize,
+    },
+    Leaf {
+        points: &'a [(T, U, V)],
+    },
+}
+
+/// How [`DNode::visit`] carries on after a callback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VisitAction {
+    Continue,
+    /// Don't descend into the children of the node just visited. Same as
+    /// `Continue` after a leaf.
+    SkipSubtree,
+    /// End the traversal right away.
+    Stop,
+}
+
+impl<const D: usize, T: Vectorial, U, V> DNode<D, T, U, V> {
+    /// Walks the tree depth-first, children in index order, letting `f` prune
+    /// subtrees or end the walk. Empty slots are not visited.
+    pub fn visit<F>(&self, f: &mut F)
+    where
+        F: FnMut(&Aabb<T>, VisitItem<'_, T, U, V>) -> VisitAction,
+    {
+        self.visit_in(f);
+    }
+
+    /// Returns `false` once the walk has been stopped.
+    fn visit_in<F>(&self, f: &mut F) -> bool
+    where
+        F: FnMut(&Aabb<T>, VisitItem<'_, T, U, V>) -> VisitAction,
+    {
+        match self {
+            DNode::None => true,
+            DNode::Leaf { area, points } => {
+                f(area, VisitItem::Leaf { points }) != VisitAction::Stop
+            }
+            DNode::Node {
+                area,
+                metadata,
+                len,
+                children,
+                ..
+            } => match f(
+                area,
+                VisitItem::Node {
+                    metadata,
+                    len: *len,
+                },
+            ) {
+                VisitAction::Continue => children.iter().all(|c| c.visit_in(f)),
+                VisitAction::SkipSubtree => true,
+                VisitAction::Stop => false,
+            },
+        }
+    }
+}
+
+#[test]
+fn test_visit_query_area() {
+    use crate::vector::DefaultVector;
+    let (tree, _) = super::random_tree(300, 43, 2);
+    let query = Aabb::new(DefaultVector([0.2, 0.1]), DefaultVector([0.6, 0.45]));
+
+    let mut found = Vec::new();
+    tree.root.visit(&mut |area, item| {
+        if !DefaultVector::intersects(*area, query) {
+            return VisitAction::SkipSubtree;
+        }
+        if let VisitItem::Leaf { points } = item {
+            found.extend(
+                points
+                    .iter()
+                    .filter(|(p, ..)| p.within(query))
+                    .map(|(.., i)| *i),
+            );
+        }
+        VisitAction::Continue
+    });
+    let mut expected: Vec<_> = tree.query_area(query).iter().map(|(.., i)| **i).collect();
+    found.sort();
+    expected.sort();
+    assert_eq!(found, expected);
+
+    // Stopping at the first leaf visits exactly one.
+    let mut leaves = 0;
+    tree.root.visit(&mut |_, item| match item {
+        VisitItem::Leaf { .. } => {
+            leaves += 1;
+            VisitAction::Stop
+        }
+        VisitItem::Node { .. } => VisitAction::Continue,
+    });
+    assert_eq!(leaves, 1);
+}
@@ -0,0 +1,1710 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::aabb::Aabb;
+use crate::metadata::{DefaultMetadata, NodeMetadata};
+use crate::naive::{
+    DNode, DepthIter, InsertError, IntoIter, LeafIter, LeafIterMut, Limits, SvgOptions, TreeStats,
+};
+use crate::scalar::Scalar;
+use crate::split::{EqualSplit, SplitStrategy};
+use crate::vector::{DefaultVector, Vectorial, check_child_count};
+
+mod builder;
+
+pub use builder::{BuildError, QuadTreeBuilder};
+
+/// Points a leaf holds before it is split, unless configured otherwise.
+pub const DEFAULT_BUCKET_SIZE: usize = 8;
+
+/// Levels of nodes below the root, unless configured otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// What [`QuadTree::insert`] does with a point at a position already taken.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DuplicatePolicy {
+    /// Store both, leaves then hold several points at the same position.
+    #[default]
+    Allow,
+    /// Fail with [`InsertError::Duplicate`].
+    Reject,
+    /// Overwrite the metadata and data of the stored point, handing back its
+    /// old data. Interior node metadata is left as it was.
+    Replace,
+}
+
+/// Why a `Vec` of points couldn't be turned into a [`QuadTree`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryFromError {
+    /// There are no points to derive the area from.
+    EmptyInput,
+    /// A position has an infinite or `NaN` coordinate.
+    NonFinite,
+}
+
+impl core::fmt::Display for TryFromError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TryFromError::EmptyInput => {
+                write!(f, "cannot derive the area of a tree from no points")
+            }
+            TryFromError::NonFinite => write!(f, "a position has a non-finite coordinate"),
+        }
+    }
+}
+
+impl core::error::Error for TryFromError {}
+
+/// Public entry point: a tree covering a fixed area, hiding the `DNode`
+/// variants and keeping track of how many points it holds. Overflowing leaves
+/// are split into the cells chosen by `S`, `M` derives the metadata of the
+/// resulting interior nodes.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "T: serde::Serialize, U: serde::Serialize, V: serde::Serialize"))
+)]
+pub struct QuadTree<const D: usize, T: Vectorial, U, V, S = EqualSplit, M = DefaultMetadata> {
+    pub(crate) area: Aabb<T>,
+    pub(crate) root: DNode<D, T, U, V>,
+    len: usize,
+    limits: Limits,
+    duplicates: DuplicatePolicy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    strategy: PhantomData<(S, M)>,
+}
+
+/// Tree over 3-D space, splitting cells into their eight octants.
+pub type OcTree<T, U, V> = QuadTree<8, T, U, V>;
+
+impl<const D: usize, T: Vectorial, U, V, S: SplitStrategy<D, T>, M: NodeMetadata<U>>
+    QuadTree<D, T, U, V, S, M>
+{
+    pub fn new(bounds: impl Into<Aabb<T>>) -> Self {
+        Self::with_bucket_size(bounds, DEFAULT_BUCKET_SIZE)
+    }
+
+    /// A tree whose leaves hold up to `bucket_size` points before splitting.
+    /// Points sharing one position are never split apart, so a leaf holding
+    /// only those may grow past it.
+    pub fn with_bucket_size(bounds: impl Into<Aabb<T>>, bucket_size: usize) -> Self {
+        Self::with_limits(bounds, bucket_size, DEFAULT_MAX_DEPTH)
+    }
+
+    /// Like [`QuadTree::with_bucket_size`], additionally stopping subdivision
+    /// `max_depth` levels below the root. Leaves at that depth keep taking
+    /// points however many there are, which bounds the work done for points
+    /// too close together for midpoint splits to separate them in practice.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `bucket_size` is zero or `D` doesn't match
+    /// [`Vectorial::DIMENSION`].
+    pub fn with_limits(bounds: impl Into<Aabb<T>>, bucket_size: usize, max_depth: usize) -> Self {
+        assert!(bucket_size > 0, "leaves must be able to hold a point");
+        check_child_count::<D, T>();
+        Self {
+            area: bounds.into(),
+            root: DNode::None,
+            len: 0,
+            limits: Limits {
+                capacity: bucket_size,
+                max_depth,
+            },
+            duplicates: DuplicatePolicy::Allow,
+            strategy: PhantomData,
+        }
+    }
+
+    /// A tree with the default limits holding `points`, built top-down in one
+    /// pass instead of inserting them one by one. Points outside `bounds` or
+    /// with non-finite positions are handed back alongside the tree. With
+    /// [`EqualSplit`] the tree has the same shape as after inserting the
+    /// points in any order.
+    pub fn bulk_build(
+        mut points: Vec<(T, U, V)>,
+        bounds: impl Into<Aabb<T>>,
+    ) -> (Self, Vec<(T, U, V)>) {
+        let mut tree = Self::new(bounds);
+        let rejects = reject(&mut points, tree.area);
+        tree.len = points.len();
+        tree.root = DNode::build::<S, M>(tree.area, points, tree.limits, 0);
+        (tree, rejects)
+    }
+
+    /// Makes the tree handle points at taken positions according to `policy`
+    /// instead of storing them alongside, see [`DuplicatePolicy`].
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicates = policy;
+        self
+    }
+
+    /// Returns the data of the point replaced under
+    /// [`DuplicatePolicy::Replace`], `None` when nothing was replaced. The tree
+    /// is left untouched on errors. The position can be anything converting
+    /// into `T`, like an array for a [`DefaultVector`].
+    pub fn insert(
+        &mut self,
+        position: impl Into<T>,
+        metadata: U,
+        data: V,
+    ) -> Result<Option<V>, InsertError>
+    where
+        T: PartialEq,
+    {
+        let position = position.into();
+        if !position.is_finite() {
+            return Err(InsertError::NonFinite);
+        }
+        match self.duplicates {
+            DuplicatePolicy::Allow => {}
+            DuplicatePolicy::Reject if self.root.contains(&position) => {
+                return Err(InsertError::Duplicate);
+            }
+            DuplicatePolicy::Reject => {}
+            DuplicatePolicy::Replace => {
+                if let Some(point) = self.root.find_mut(&position) {
+                    point.1 = metadata;
+                    return Ok(Some(core::mem::replace(&mut point.2, data)));
+                }
+            }
+        }
+        self.root
+            .insert_point::<S, M>(self.area, (position, metadata, data), self.limits, 0)?;
+        self.len += 1;
+        Ok(None)
+    }
+
+    /// Removes one point stored at exactly `position`.
+    pub fn remove(&mut self, position: &T) -> Option<V>
+    where
+        T: PartialEq,
+    {
+        let (_, _, data) = self.root.remove_point(position, self.limits.capacity)?;
+        self.len -= 1;
+        Some(data)
+    }
+
+    /// See [`DNode::retain`].
+    pub fn retain<F: FnMut(&T, &U, &V) -> bool>(&mut self, mut f: F) {
+        self.len -= self.root.retain_points(&mut f, self.limits.capacity);
+    }
+
+    /// See [`DNode::relocate`]. A point already at `new_position` is handled
+    /// by the duplicate policy: under [`DuplicatePolicy::Reject`] nothing
+    /// moves and `false` comes back, under [`DuplicatePolicy::Replace`] the
+    /// moved point's metadata and data overwrite its own.
+    pub fn relocate(&mut self, old_position: &T, new_position: T) -> bool
+    where
+        T: PartialEq,
+    {
+        if *old_position != new_position && self.root.contains(&new_position) {
+            match self.duplicates {
+                DuplicatePolicy::Allow => {}
+                DuplicatePolicy::Reject => return false,
+                DuplicatePolicy::Replace => {
+                    let capacity = self.limits.capacity;
+                    let Some((_, metadata, data)) = self.root.remove_point(old_position, capacity)
+                    else {
+                        return false;
+                    };
+                    self.len -= 1;
+                    let point = self.root.find_mut(&new_position).expect("still stored");
+                    (point.1, point.2) = (metadata, data);
+                    return true;
+                }
+            }
+        }
+        self.root
+            .relocate_point::<S, M>(old_position, new_position, self.limits)
+    }
+
+    /// Runs [`DNode::split_at_median`] with this tree's strategy and limits on
+    /// the subtree reached by following the child indices `path` from the
+    /// root, as in [`ValidationError::path`](crate::ValidationError::path).
+    /// Returns `false` when `path` leads anywhere else than a node or leaf.
+    pub fn split_at_median(&mut self, path: &[usize]) -> bool {
+        let mut node = &mut self.root;
+        for &i in path {
+            match node {
+                DNode::Node { children, .. } if i < D => node = &mut *children[i],
+                _ => return false,
+            }
+        }
+        if matches!(node, DNode::None) {
+            return false;
+        }
+        node.split_at_median_in::<S, M>(self.limits, path.len());
+        true
+    }
+
+    /// Moves every point of `other` into `self`. When the bounds of `other`
+    /// reach past those of `self`, `self` first grows to cover both and is
+    /// rebuilt with [`QuadTree::bulk_build`]'s partitioning, so no point is
+    /// ever rejected for lying outside. Points at positions already taken are
+    /// handled by the duplicate policy of `self`, under
+    /// [`DuplicatePolicy::Reject`] the ones from `other` are dropped.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: PartialEq,
+    {
+        let (lo, hi) = (other.area.min(), other.area.max());
+        if !(lo.within(self.area) && hi.within(self.area)) {
+            let points = self.root.drain().collect();
+            self.area = self.area.union(&other.area);
+            self.root = DNode::build::<S, M>(self.area, points, self.limits, 0);
+        }
+        for point in other {
+            self.insert_or_skip(point);
+        }
+    }
+
+    /// Inserts `point`, dropping it when the duplicate policy rejects it.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the position is out of bounds or not finite.
+    fn insert_or_skip(&mut self, (position, metadata, data): (T, U, V))
+    where
+        T: PartialEq,
+    {
+        match self.insert(position, metadata, data) {
+            Ok(_) | Err(InsertError::Duplicate) => {}
+            Err(e) => panic!("cannot insert point: {e}"),
+        }
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V, S, M> QuadTree<D, T, U, V, S, M> {
+    pub fn bucket_size(&self) -> usize {
+        self.limits.capacity
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.limits.max_depth
+    }
+
+    /// Number of stored points, kept up to date by insertions and removals.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn bounds(&self) -> Aabb<T> {
+        self.area
+    }
+
+    /// See [`DNode::stats`].
+    pub fn stats(&self) -> TreeStats {
+        self.root.stats()
+    }
+
+    /// Alias of [`QuadTree::stats`].
+    pub fn stat(&self) -> TreeStats {
+        self.stats()
+    }
+
+    /// Drops every stored point, keeping the bounds and settings.
+    pub fn clear(&mut self) {
+        self.root.clear();
+        self.len = 0;
+    }
+
+    /// Number of levels on the longest path from the root down to a leaf,
+    /// the leaf included: 0 for an empty tree, 1 while the root is a leaf.
+    /// [`DNode::depth`] counts the interior nodes only.
+    pub fn depth(&self) -> usize {
+        match self.root {
+            DNode::None => 0,
+            _ => self.root.depth() + 1,
+        }
+    }
+
+    /// See [`DNode::bounding_box`].
+    pub fn bounding_box(&self) -> Option<Aabb<T>> {
+        self.root.bounding_box()
+    }
+
+    /// See [`DNode::contains`].
+    pub fn contains(&self, position: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.root.contains(position)
+    }
+
+    /// See [`DNode::update_metadata`].
+    pub fn update_metadata<F: FnMut(&mut U)>(&mut self, position: &T, f: F) -> bool
+    where
+        T: PartialEq,
+    {
+        self.root.update_metadata(position, f)
+    }
+
+    /// Iterates over every stored point, see [`DNode::iter`].
+    pub fn iter(&self) -> LeafIter<'_, D, T, U, V> {
+        self.root.iter()
+    }
+
+    /// Iterates over every stored point along with the depth of its leaf, see
+    /// [`DNode::iter_with_depth`].
+    pub fn iter_with_depth(&self) -> DepthIter<'_, D, T, U, V> {
+        self.root.iter_with_depth()
+    }
+
+    /// Iterates over every stored point with mutable access to its payload,
+    /// see [`DNode::iter_mut`]. Use [`QuadTree::relocate`] to move points.
+    pub fn iter_mut(&mut self) -> LeafIterMut<'_, D, T, U, V> {
+        self.root.iter_mut()
+    }
+
+    /// Moves every stored point out, see [`DNode::drain`]. The tree keeps its
+    /// area and settings and can be filled again right away.
+    pub fn drain(&mut self) -> IntoIter<D, T, U, V> {
+        self.len = 0;
+        self.root.drain()
+    }
+
+    /// Every stored point within the axis-aligned box `area`, boundaries included.
+    pub fn query_area(&self, area: impl Into<Aabb<T>>) -> Vec<(&T, &U, &V)> {
+        self.root.query_range(area.into())
+    }
+
+    /// Calls `f` on every stored point within `area` to change its metadata
+    /// and data in place, see [`DNode::for_each_in_range`].
+    pub fn for_each_in_range<F: FnMut(&T, &mut U, &mut V)>(
+        &mut self,
+        area: impl Into<Aabb<T>>,
+        f: F,
+    ) {
+        self.root.for_each_in_range(area.into(), f);
+    }
+
+    /// Number of stored points within `area`, see [`DNode::count_in_area`].
+    pub fn count_in_area(&self, area: impl Into<Aabb<T>>) -> usize {
+        self.root.count_in_area(area.into())
+    }
+
+    /// The stored point closest to `target` along with its data.
+    pub fn nearest(&self, target: T) -> Option<(&T, &V)> {
+        self.root
+            .nearest(&target)
+            .map(|(position, _, data)| (position, data))
+    }
+
+    /// Every stored point at most `radius` away from `center`, boundary included.
+    pub fn within_radius(&self, center: T, radius: f64) -> Vec<(&T, &V)> {
+        let mut found = Vec::new();
+        self.root
+            .for_each_within_radius(&center, radius, |position, _, data| {
+                found.push((position, data))
+            });
+        found
+    }
+
+    /// Like [`QuadTree::within_radius`], with the metadata of each point too.
+    /// Queries the box around the circle, see [`DNode::query_radius`].
+    pub fn query_radius(&self, center: T, radius: f64) -> Vec<(&T, &U, &V)> {
+        self.root.query_radius(&center, radius)
+    }
+
+    /// Every stored point at most `radius` away from the segment
+    /// `origin + direction * t` with `t` in `0..=max_t`, and the `t` closest
+    /// to it, sorted by that. A `radius` of `0.0` only finds points right on
+    /// the segment. Negative or `NaN` values of `radius` or `max_t` find nothing.
+    pub fn query_ray(
+        &self,
+        origin: T,
+        direction: T,
+        max_t: f64,
+        radius: f64,
+    ) -> Vec<(&T, &V, f64)> {
+        self.root
+            .query_ray(&origin, &direction, max_t, radius)
+            .into_iter()
+            .map(|(position, _, data, t)| (position, data, t))
+            .collect()
+    }
+
+    /// The first point [`QuadTree::query_ray`] would return, without searching
+    /// past it.
+    pub fn query_ray_first(
+        &self,
+        origin: T,
+        direction: T,
+        max_t: f64,
+        radius: f64,
+    ) -> Option<(&T, &V, f64)> {
+        self.root
+            .query_ray_first(&origin, &direction, max_t, radius)
+            .map(|(position, _, data, t)| (position, data, t))
+    }
+
+    /// Like [`QuadTree::within_radius`] without collecting the matches.
+    pub fn for_each_within_radius<'a, F>(&'a self, center: T, radius: f64, mut f: F)
+    where
+        F: FnMut(&'a T, &'a V),
+    {
+        self.root
+            .for_each_within_radius(&center, radius, |position, _, data| f(position, data));
+    }
+
+    /// Up to `k` stored points sorted by increasing distance to `target`.
+    pub fn knn(&self, target: T, k: usize) -> Vec<(&T, &V)> {
+        self.root
+            .k_nearest(&target, k)
+            .into_iter()
+            .map(|(position, _, data)| (position, data))
+            .collect()
+    }
+}
+
+impl<const D: usize, U, V, S, M> QuadTree<D, DefaultVector<2>, U, V, S, M> {
+    /// See [`DNode::to_svg`].
+    pub fn to_svg(&self, options: SvgOptions) -> String {
+        self.root.to_svg(options)
+    }
+}
+
+impl<const D: usize, const N: usize, S: Scalar, U, V, M: NodeMetadata<U>>
+    QuadTree<D, DefaultVector<N, S>, U, V, EqualSplit, M>
+{
+    /// Like [`QuadTree::bulk_build`], sorting the points by
+    /// [`DefaultVector::morton_code`] first. Each node then finds the points
+    /// of its children by binary search, and the points of every leaf end up
+    /// next to each other.
+    pub fn bulk_build_morton(
+        mut points: Vec<(DefaultVector<N, S>, U, V)>,
+        bounds: impl Into<Aabb<DefaultVector<N, S>>>,
+    ) -> (Self, Vec<(DefaultVector<N, S>, U, V)>) {
+        let mut tree = Self::new(bounds);
+        let rejects = reject(&mut points, tree.area);
+        let bits = 64 / N.max(1) as u32;
+        points.sort_by_cached_key(|(p, ..)| p.morton_code(&tree.area, bits));
+        tree.len = points.len();
+        tree.root =
+            DNode::build_sorted::<EqualSplit, M>(tree.area, points, tree.limits, 0, bits as usize);
+        (tree, rejects)
+    }
+}
+
+/// Takes the points a tree covering `area` can't hold out of `points`.
+fn reject<T: Vectorial, U, V>(points: &mut Vec<(T, U, V)>, area: Aabb<T>) -> Vec<(T, U, V)> {
+    points
+        .extract_if(.., |(p, ..)| !(p.is_finite() && p.within(area)))
+        .collect()
+}
+
+/// Smallest box enclosing all of `points`, `None` without any.
+fn bounding_box_of<T: Vectorial, U, V>(points: &[(T, U, V)]) -> Option<Aabb<T>> {
+    points
+        .iter()
+        .map(|(p, ..)| Aabb::new(*p, *p))
+        .reduce(|a, b| a.union(&b))
+}
+
+/// Builds a tree whose area is the bounding box of all positions, or the box
+/// from [`Vectorial::zero`] to [`Vectorial::ones`] when there are none. Points
+/// with non-finite positions are skipped, collect into a `Vec` and use
+/// `try_from` to get an error instead.
+impl<const D: usize, T, U, V, S, M> FromIterator<(T, U, V)> for QuadTree<D, T, U, V, S, M>
+where
+    T: Vectorial + PartialEq,
+    S: SplitStrategy<D, T>,
+    M: NodeMetadata<U>,
+{
+    fn from_iter<I: IntoIterator<Item = (T, U, V)>>(iter: I) -> Self {
+        let mut points: Vec<_> = iter.into_iter().collect();
+        points.retain(|(p, ..)| p.is_finite());
+        let bounds = bounding_box_of(&points).unwrap_or(Aabb::new(T::zero(), T::ones()));
+        let mut tree = Self::new(bounds);
+        for (position, metadata, data) in points {
+            tree.insert(position, metadata, data)
+                .expect("the bounding box contains every point");
+        }
+        tree
+    }
+}
+
+/// Bulk builds a tree like [`QuadTree::bulk_build`], over the bounding box
+/// of all positions.
+impl<const D: usize, T, U, V, S, M> TryFrom<Vec<(T, U, V)>> for QuadTree<D, T, U, V, S, M>
+where
+    T: Vectorial,
+    S: SplitStrategy<D, T>,
+    M: NodeMetadata<U>,
+{
+    type Error = TryFromError;
+
+    fn try_from(points: Vec<(T, U, V)>) -> Result<Self, TryFromError> {
+        let bounds = bounding_box_of(&points).ok_or(TryFromError::EmptyInput)?;
+        let (tree, rejects) = Self::bulk_build(points, bounds);
+        if !rejects.is_empty() {
+            return Err(TryFromError::NonFinite);
+        }
+        Ok(tree)
+    }
+}
+
+/// Inserts every point like [`QuadTree::insert`], dropping the ones the
+/// duplicate policy rejects.
+///
+/// # Panics
+///
+/// Panics on points out of bounds or with non-finite positions, use
+/// [`QuadTree::bulk_build`] to get those back instead.
+impl<const D: usize, T, U, V, S, M> Extend<(T, U, V)> for QuadTree<D, T, U, V, S, M>
+where
+    T: Vectorial + PartialEq,
+    S: SplitStrategy<D, T>,
+    M: NodeMetadata<U>,
+{
+    fn extend<I: IntoIterator<Item = (T, U, V)>>(&mut self, iter: I) {
+        for point in iter {
+            self.insert_or_skip(point);
+        }
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V, S, M> IntoIterator for QuadTree<D, T, U, V, S, M> {
+    type Item = (T, U, V);
+    type IntoIter = IntoIter<D, T, U, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.root.into_iter()
+    }
+}
+
+impl<'a, const D: usize, T: Vectorial, U, V, S, M> IntoIterator for &'a QuadTree<D, T, U, V, S, M> {
+    type Item = (&'a T, &'a U, &'a V);
+    type IntoIter = LeafIter<'a, D, T, U, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, const D: usize, T: Vectorial, U, V, S, M> IntoIterator
+    for &'a mut QuadTree<D, T, U, V, S, M>
+{
+    type Item = (&'a T, &'a mut U, &'a mut V);
+    type IntoIter = LeafIterMut<'a, D, T, U, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// The fields of a [`QuadTree`] as serialized, checked before becoming one.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(
+    rename = "QuadTree",
+    bound(
+        deserialize = "T: serde::Deserialize<'de>, U: serde::Deserialize<'de>, V: serde::Deserialize<'de>"
+    )
+)]
+struct Stored<const D: usize, T: Vectorial, U, V> {
+    area: Aabb<T>,
+    root: DNode<D, T, U, V>,
+    len: usize,
+    limits: Limits,
+    duplicates: DuplicatePolicy,
+}
+
+/// Fails with the [`ValidationError`](crate::ValidationError) of a tree that
+/// couldn't have been built through insertions, rather than handing out one
+/// whose queries go wrong.
+#[cfg(feature = "serde")]
+impl<'de, const D: usize, T, U, V, S, M> serde::Deserialize<'de> for QuadTree<D, T, U, V, S, M>
+where
+    T: Vectorial + serde::Deserialize<'de>,
+    U: serde::Deserialize<'de>,
+    V: serde::Deserialize<'de>,
+{
+    fn deserialize<De: serde::Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        use crate::naive::{InvalidTree, ValidationError};
+        use serde::de::{Error, Unexpected};
+        check_child_count::<D, T>();
+        let stored = Stored::<D, T, U, V>::deserialize(deserializer)?;
+        if stored.limits.capacity == 0 {
+            return Err(Error::invalid_value(
+                Unexpected::Unsigned(0),
+                &"a positive bucket size",
+            ));
+        }
+        let fail = |invariant| {
+            Error::custom(ValidationError {
+                invariant,
+                path: Vec::new(),
+            })
+        };
+        stored.root.validate().map_err(Error::custom)?;
+        let same = |p: T, q: T| p.within(Aabb::new(q, q));
+        if let Some(area) = stored.root.area()
+            && !(same(area.min(), stored.area.min()) && same(area.max(), stored.area.max()))
+        {
+            return Err(fail(InvalidTree::ChildOutsideCell));
+        }
+        if stored.root.len() != stored.len {
+            return Err(fail(InvalidTree::CountMismatch));
+        }
+        Ok(Self {
+            area: stored.area,
+            root: stored.root,
+            len: stored.len,
+            limits: stored.limits,
+            duplicates: stored.duplicates,
+            strategy: PhantomData,
+        })
+    }
+}
+
+#[test]
+fn test_quadtree_insert() {
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), u32>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    tree.insert(DefaultVector([0.5, 0.5]), (), 7).unwrap();
+    assert!(matches!(&tree.root, DNode::Leaf { points, .. } if points[0].2 == 7));
+    // Anything converting into a DefaultVector goes in directly.
+    tree.insert([0.25, 0.75], (), 8).unwrap();
+    tree.insert((0.75, 0.25), (), 9).unwrap();
+    let found = tree.query_area(([0.0, 0.6].into(), DefaultVector([0.4, 1.0])));
+    assert_eq!(found, [(&DefaultVector([0.25, 0.75]), &(), &8)]);
+}
+
+#[test]
+fn test_query_area_edges() {
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), ()>::new((DefaultVector([0.0, 0.0]), DefaultVector([4.0, 4.0])));
+    for x in 0..=4 {
+        for y in 0..=4 {
+            tree.insert(DefaultVector([x as f64, y as f64]), (), ())
+                .unwrap();
+        }
+    }
+    let count = |lo: [f64; 2], hi: [f64; 2]| {
+        tree.query_area((DefaultVector(lo), DefaultVector(hi)))
+            .len()
+    };
+    // Boxes touching the split plane x = 2 from either side still see the points on it.
+    assert_eq!(count([1.5, 0.0], [2.0, 4.0]), 5);
+    assert_eq!(count([2.0, 0.0], [2.5, 4.0]), 5);
+    // Touching the outer face of the root from outside.
+    assert_eq!(count([4.0, 4.0], [5.0, 5.0]), 1);
+    // Degenerate boxes: a line and a single point.
+    assert_eq!(count([0.0, 2.0], [4.0, 2.0]), 5);
+    assert_eq!(count([3.0, 1.0], [3.0, 1.0]), 1);
+    assert_eq!(count([0.5, 0.5], [0.5, 3.5]), 0);
+}
+
+#[test]
+fn test_quadtree_nearest() {
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), char>::new((DefaultVector([0.0, 0.0]), DefaultVector([8.0, 8.0])));
+    assert_eq!(tree.nearest(DefaultVector([1.0, 1.0])), None);
+    tree.insert(DefaultVector([1.0, 7.0]), (), 'a').unwrap();
+    tree.insert(DefaultVector([6.0, 6.0]), (), 'b').unwrap();
+    tree.insert(DefaultVector([7.5, 0.5]), (), 'c').unwrap();
+    let (position, data) = tree.nearest(DefaultVector([4.1, 4.0])).unwrap();
+    assert_eq!((*position, *data), (DefaultVector([6.0, 6.0]), 'b'));
+}
+
+#[test]
+fn test_knn_matches_brute_force() {
+    let (tree, points) = crate::naive::random_tree(1000, 5, DEFAULT_BUCKET_SIZE);
+    for target in crate::naive::random_points(20, 6) {
+        let mut expected: Vec<usize> = (0..points.len()).collect();
+        expected.sort_by(|&a, &b| {
+            points[a]
+                .distance_squared(&target)
+                .total_cmp(&points[b].distance_squared(&target))
+        });
+        let found: Vec<usize> = tree.knn(target, 25).iter().map(|(_, i)| **i).collect();
+        assert_eq!(found, expected[..25]);
+    }
+}
+
+#[test]
+fn test_within_radius() {
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), usize>::new((DefaultVector([0.0, 0.0]), DefaultVector([4.0, 4.0])));
+    for x in 0..=4 {
+        for y in 0..=4 {
+            tree.insert(DefaultVector([x as f64, y as f64]), (), x * 5 + y)
+                .unwrap();
+        }
+    }
+    let center = DefaultVector([2.0, 2.0]);
+    // Distance exactly 1 is included: the center and its four neighbours.
+    assert_eq!(tree.within_radius(center, 1.0).len(), 5);
+    assert_eq!(tree.within_radius(center, 0.0), [(&center, &12)]);
+    assert!(
+        tree.within_radius(DefaultVector([2.5, 2.5]), 0.0)
+            .is_empty()
+    );
+
+    // Exactly at the distance of the points (3, 4) and (4, 3) from the origin.
+    let found = tree.within_radius(DefaultVector([0.0, 0.0]), 5.0);
+    assert_eq!(found.len(), 24);
+    assert!(found.contains(&(&DefaultVector([3.0, 4.0]), &19)));
+    assert!(tree.within_radius(center, -1.0).is_empty());
+    assert!(tree.within_radius(center, f64::NAN).is_empty());
+
+    let mut count = 0;
+    tree.for_each_within_radius(DefaultVector([0.0, 0.0]), 2.0, |_, _| count += 1);
+    assert_eq!(count, 6);
+    tree.for_each_within_radius(center, -2.0, |_, _| count += 1);
+    assert_eq!(count, 6);
+
+    // Same as filtering the box around the circle by distance.
+    let center = DefaultVector([1.3, 2.6]);
+    let mut found: Vec<_> = tree
+        .query_radius(center, 1.5)
+        .iter()
+        .map(|(.., i)| **i)
+        .collect();
+    let lo = DefaultVector([-0.2, 1.1]);
+    let hi = DefaultVector([2.8, 4.1]);
+    let mut expected: Vec<_> = tree
+        .query_area((lo, hi))
+        .into_iter()
+        .filter(|(p, ..)| p.distance_squared(&center) <= 1.5 * 1.5)
+        .map(|(.., i)| *i)
+        .collect();
+    found.sort();
+    expected.sort();
+    assert_eq!(found, expected);
+    assert_eq!(found.len(), 7);
+    for radius in [0.0, 1.0, 2.5, 5.0] {
+        let center = DefaultVector([3.0, 1.0]);
+        assert_eq!(
+            tree.query_radius(center, radius).len(),
+            tree.within_radius(center, radius).len()
+        );
+    }
+    assert!(tree.query_radius(center, -1.5).is_empty());
+    assert!(tree.query_radius(center, f64::NAN).is_empty());
+}
+
+#[test]
+fn test_relocate() {
+    use crate::vector::DefaultVector;
+    let mut tree = crate::naive::unit_tree::<(), usize>(1);
+    tree.insert(DefaultVector([0.1, 0.1]), (), 0).unwrap();
+    tree.insert(DefaultVector([0.9, 0.9]), (), 1).unwrap();
+    assert!(!tree.relocate(&DefaultVector([0.5, 0.5]), DefaultVector([0.2, 0.2])));
+    assert!(!tree.relocate(&DefaultVector([0.1, 0.1]), DefaultVector([2.0, 0.2])));
+
+    assert!(tree.relocate(&DefaultVector([0.1, 0.1]), DefaultVector([0.2, 0.3])));
+    assert!(tree.relocate(&DefaultVector([0.9, 0.9]), DefaultVector([0.3, 0.2])));
+    let mut found: Vec<_> = tree.root.iter().map(|(p, _, i)| (**p, *i)).collect();
+    found.sort_by_key(|(_, i)| *i);
+    assert_eq!(found, [([0.2, 0.3], 0), ([0.3, 0.2], 1)]);
+    tree.root.assert_valid();
+
+    // Moving onto an occupied position keeps both points under the default
+    // `DuplicatePolicy::Allow`, see `test_relocate_duplicate_policies` for
+    // the others.
+    assert!(tree.relocate(&DefaultVector([0.2, 0.3]), DefaultVector([0.3, 0.2])));
+    assert_eq!(
+        tree.query_area((DefaultVector([0.3, 0.2]), DefaultVector([0.3, 0.2])))
+            .len(),
+        2
+    );
+    tree.root.assert_valid();
+}
+
+#[test]
+fn test_split_at_median() {
+    // Spread along x within a thin band, splitting at midpoints would take
+    // several levels to separate them along y.
+    let points: Vec<_> = crate::naive::random_points(200, 19)
+        .into_iter()
+        .map(|p| DefaultVector([p[0], 0.5 + p[1] * 0.01]))
+        .collect();
+    let mut tree = crate::naive::unit_tree::<(), usize>(4);
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    assert!(!tree.split_at_median(&[4]));
+    assert!(!tree.split_at_median(&[0, 0, 0, 0, 0, 0]));
+    assert!(tree.split_at_median(&[]));
+    tree.root.assert_valid();
+    assert_eq!((tree.len(), tree.root.len()), (200, 200));
+    assert_eq!((tree.root[2].len(), tree.root[3].len()), (100, 100));
+    // The halves are split further under the tree's own bucket size.
+    tree.root.visit(&mut |_, item| {
+        if let crate::VisitItem::Leaf { points } = item {
+            assert!(points.len() <= 4);
+        }
+        crate::VisitAction::Continue
+    });
+    let query = (DefaultVector([0.2, 0.0]), DefaultVector([0.3, 1.0]));
+    let expected = points
+        .iter()
+        .filter(|p| (0.2..=0.3).contains(&p[0]))
+        .count();
+    assert_eq!(tree.query_area(query).len(), expected);
+}
+
+#[test]
+fn test_relocate_stress() {
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), usize>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    // The full 10k points take a while unoptimized, `cargo test --release` runs them.
+    let n = if cfg!(debug_assertions) {
+        1_000
+    } else {
+        10_000
+    };
+    let mut points = crate::naive::random_points(n, 9);
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    // Bounce off the faces of the unit square to stay inside the tree.
+    let reflect = |x: f64| {
+        if x < 0.0 {
+            -x
+        } else if x > 1.0 {
+            2.0 - x
+        } else {
+            x
+        }
+    };
+    for round in 0..100 {
+        let jitter = crate::naive::random_points(points.len(), 10 + round);
+        for (p, j) in points.iter_mut().zip(jitter) {
+            let moved = DefaultVector(std::array::from_fn(|k| reflect(p[k] + (j[k] - 0.5) * 0.02)));
+            assert!(tree.relocate(p, moved));
+            *p = moved;
+        }
+    }
+    assert_eq!(tree.root.len(), points.len());
+    tree.root.assert_valid();
+    for (i, p) in points.iter().enumerate() {
+        let found: Vec<_> = tree
+            .query_area((*p, *p))
+            .iter()
+            .map(|(_, _, d)| **d)
+            .collect();
+        assert_eq!(found, [i]);
+    }
+}
+
+#[test]
+fn test_len_tracks_insert_and_remove() {
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), ()>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    assert!(tree.is_empty());
+    let points = crate::naive::random_points(50, 11);
+    for p in &points {
+        tree.insert(*p, (), ()).unwrap();
+    }
+    // Out of bounds points don't count, repeated positions do.
+    assert!(tree.insert(DefaultVector([2.0, 0.0]), (), ()).is_err());
+    tree.insert(points[0], (), ()).unwrap();
+    assert_eq!(tree.len(), 51);
+    assert_eq!(tree.len(), tree.root.len());
+
+    for p in &points[..20] {
+        tree.remove(p).unwrap();
+    }
+    assert!(tree.remove(&points[0]).is_some());
+    assert!(tree.remove(&points[0]).is_none());
+    assert_eq!(tree.len(), 30);
+    assert_eq!(tree.len(), tree.root.len());
+}
+
+#[test]
+fn test_bucket_size() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut tree = QuadTree::<4, _, (), usize>::with_bucket_size(area, 4);
+    let points = crate::naive::random_points(200, 13);
+    for (i, p) in points[..4].iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    assert!(matches!(&tree.root, DNode::Leaf { points, .. } if points.len() == 4));
+    tree.insert(points[4], (), 4).unwrap();
+    assert!(matches!(tree.root, DNode::Node { .. }));
+
+    for (i, p) in points.iter().enumerate().skip(5) {
+        tree.insert(*p, (), i).unwrap();
+    }
+    for (i, p) in points.iter().enumerate() {
+        let found: Vec<_> = tree
+            .query_area((*p, *p))
+            .iter()
+            .map(|(_, _, d)| **d)
+            .collect();
+        assert_eq!(found, [i]);
+    }
+    tree.root.assert_valid();
+
+    // Removing everything but a handful merges the buckets back together.
+    for p in &points[3..] {
+        tree.remove(p).unwrap();
+    }
+    assert!(matches!(&tree.root, DNode::Leaf { points, .. } if points.len() == 3));
+}
+
+#[test]
+fn test_bucket_same_position() {
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), usize>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    let p = DefaultVector([0.3, 0.3]);
+    for i in 0..100 {
+        tree.insert(p, (), i).unwrap();
+    }
+    assert_eq!(tree.len(), 100);
+    assert_eq!(tree.root.depth(), 0);
+    assert_eq!(tree.query_area((p, p)).len(), 100);
+
+    // A different position makes the bucket separable again.
+    tree.insert(DefaultVector([0.9, 0.9]), (), 100).unwrap();
+    assert_eq!(tree.root.depth(), 1);
+    assert_eq!(tree.len(), 101);
+}
+
+#[test]
+fn test_max_depth() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let a = DefaultVector([0.3, 0.3]);
+    let b = DefaultVector([0.3, f64::from_bits(0.3f64.to_bits() + 1)]);
+
+    // Nothing short of the precision limit separates these two.
+    let mut tree = QuadTree::<4, _, (), u32>::with_bucket_size(area, 1);
+    tree.insert(a, (), 0).unwrap();
+    tree.insert(b, (), 1).unwrap();
+    assert!(tree.root.depth() <= DEFAULT_MAX_DEPTH);
+    assert_eq!(tree.query_area((a, b)).len(), 2);
+
+    let mut tree = QuadTree::<4, _, (), u32>::with_limits(area, 1, 3);
+    tree.insert(a, (), 0).unwrap();
+    tree.insert(b, (), 1).unwrap();
+    tree.insert(DefaultVector([0.31, 0.31]), (), 2).unwrap();
+    assert_eq!(tree.root.depth(), 3);
+    assert_eq!(tree.query_area((a, a)).len(), 1);
+    assert_eq!(tree.query_area((b, b)).len(), 1);
+    assert_eq!(tree.query_area((a, DefaultVector([0.31, 0.31]))).len(), 3);
+    tree.root.assert_valid();
+
+    // Relocating within the full leaf must not split it further either.
+    assert!(tree.relocate(&DefaultVector([0.31, 0.31]), DefaultVector([0.32, 0.32])));
+    assert_eq!(tree.root.depth(), 3);
+    assert_eq!(tree.len(), 3);
+}
+
+#[test]
+fn test_quadtree_iter() {
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, (), usize>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    assert_eq!(tree.iter().count(), 0);
+    let points = crate::naive::random_points(200, 29);
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    let mut seen = Vec::new();
+    for (position, _, i) in &tree {
+        assert_eq!(*position, points[*i]);
+        seen.push(*i);
+    }
+    seen.sort();
+    assert_eq!(seen, (0..200).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_quadtree_iter_mut() {
+    use crate::vector::DefaultVector;
+    let mut tree =
+        QuadTree::<4, _, u32, f64>::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    for p in crate::naive::random_points(200, 37) {
+        tree.insert(p, 0, p[0]).unwrap();
+    }
+    for _ in 0..3 {
+        for (position, ticks, velocity) in &mut tree {
+            *ticks += 1;
+            *velocity += position[1];
+        }
+    }
+    assert_eq!(tree.len(), 200);
+    for (position, ticks, velocity) in tree.iter() {
+        assert_eq!(*ticks, 3);
+        assert_eq!(
+            *velocity,
+            position[0] + position[1] + position[1] + position[1]
+        );
+    }
+    tree.root.assert_valid();
+}
+
+#[test]
+fn test_from_iter() {
+    use crate::vector::DefaultVector;
+    let points = crate::naive::random_points(100, 41);
+    let tree: QuadTree<4, _, (), usize> = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (*p * 3.0, (), i))
+        .collect();
+    assert_eq!(tree.len(), 100);
+    let (lo, hi) = (tree.bounds().min(), tree.bounds().max());
+    for k in 0..2 {
+        assert_eq!(
+            lo[k],
+            points
+                .iter()
+                .map(|p| p[k] * 3.0)
+                .fold(f64::INFINITY, f64::min)
+        );
+        assert_eq!(hi[k], points.iter().map(|p| p[k] * 3.0).fold(0.0, f64::max));
+    }
+    for (position, _, i) in &tree {
+        assert_eq!(*position, points[*i] * 3.0);
+    }
+
+    let single: QuadTree<4, _, (), ()> =
+        std::iter::once((DefaultVector([1.0, 2.0]), (), ())).collect();
+    assert_eq!(
+        single.bounds(),
+        Aabb::new(DefaultVector([1.0, 2.0]), DefaultVector([1.0, 2.0]))
+    );
+    assert_eq!(single.len(), 1);
+
+    let empty: QuadTree<4, DefaultVector<2>, (), ()> = std::iter::empty().collect();
+    assert!(empty.is_empty());
+    assert_eq!(
+        empty.bounds(),
+        Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]))
+    );
+    let skipped: QuadTree<4, _, (), char> = [
+        (DefaultVector([f64::NAN, 0.0]), (), 'a'),
+        (DefaultVector([2.0, 3.0]), (), 'b'),
+        (DefaultVector([f64::INFINITY, 1.0]), (), 'c'),
+        (DefaultVector([4.0, 1.0]), (), 'd'),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(
+        skipped.bounds(),
+        Aabb::new(DefaultVector([2.0, 1.0]), DefaultVector([4.0, 3.0]))
+    );
+    assert_eq!(
+        skipped.iter().map(|(.., c)| *c).collect::<Vec<_>>(),
+        ['b', 'd']
+    );
+}
+
+#[test]
+fn test_try_from_vec() {
+    use crate::vector::DefaultVector;
+    let points: Vec<_> = crate::naive::random_points(50, 7)
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| (p, (), i))
+        .collect();
+    let collected: QuadTree<4, _, (), usize> = points.iter().copied().collect();
+    let tree = QuadTree::<4, _, (), usize>::try_from(points).unwrap();
+    tree.root.assert_valid();
+    assert_eq!(tree.len(), 50);
+    assert_eq!(tree.bounds(), collected.bounds());
+    assert_eq!(tree.bounding_box(), Some(tree.bounds()));
+
+    let empty = QuadTree::<4, DefaultVector<2>, (), ()>::try_from(vec![]);
+    assert_eq!(empty.err(), Some(TryFromError::EmptyInput));
+    let nan = vec![
+        (DefaultVector([0.0, 1.0]), (), ()),
+        (DefaultVector([f64::NAN, 0.0]), (), ()),
+    ];
+    let nan = QuadTree::<4, _, (), ()>::try_from(nan);
+    assert_eq!(nan.err(), Some(TryFromError::NonFinite));
+}
+
+#[test]
+fn test_midpoint_split_strategy() {
+    use crate::split::MidpointSplit;
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut tree = QuadTree::<4, _, (), usize, MidpointSplit>::with_bucket_size(area, 4);
+    // Clustered near the origin, equal splits would need several levels.
+    let points: Vec<_> = crate::naive::random_points(200, 47)
+        .into_iter()
+        .map(|p| p * 0.01)
+        .collect();
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    let DNode::Node { cells, .. } = &tree.root else {
+        panic!("the root should have been split");
+    };
+    assert!(cells[0].max()[0] < 0.01);
+    for (i, p) in points.iter().enumerate() {
+        let found: Vec<_> = tree
+            .query_area((*p, *p))
+            .iter()
+            .map(|(.., d)| **d)
+            .collect();
+        assert_eq!(found, [i]);
+        assert!(tree.contains(p));
+    }
+    tree.root.assert_valid();
+
+    let mut equal = QuadTree::<4, _, (), usize>::with_bucket_size(area, 4);
+    for (i, p) in points.iter().enumerate() {
+        equal.insert(*p, (), i).unwrap();
+    }
+    assert!(tree.root.depth() < equal.root.depth());
+
+    for p in &points[..150] {
+        assert!(tree.relocate(p, *p * 50.0));
+    }
+    for p in &points {
+        tree.remove(p);
+    }
+    assert_eq!(tree.len(), 150);
+    tree.root.assert_valid();
+}
+
+#[test]
+fn test_quadtree_drain() {
+    use crate::vector::DefaultVector;
+    let mut tree = QuadTree::<4, _, (), Vec<usize>>::new((
+        DefaultVector([0.0, 0.0]),
+        DefaultVector([1.0, 1.0]),
+    ));
+    let points = crate::naive::random_points(100, 53);
+    for frame in 0..3 {
+        let payloads: Vec<_> = if frame == 0 {
+            (0..100).map(|i| vec![i]).collect()
+        } else {
+            let mut drained: Vec<_> = tree.drain().map(|(.., data)| data).collect();
+            assert!(tree.is_empty());
+            drained.sort();
+            assert_eq!(drained.len(), 100);
+            drained
+        };
+        for (p, data) in points.iter().zip(payloads) {
+            tree.insert(*p, (), data).unwrap();
+        }
+        assert_eq!(tree.len(), 100);
+    }
+    // Stopping early still leaves an empty tree.
+    assert!(tree.drain().next().is_some());
+    assert!(tree.is_empty());
+    assert!(tree.iter().next().is_none());
+}
+
+#[test]
+fn test_non_clone_payload() {
+    use crate::split::EqualSplit;
+    use crate::vector::DefaultVector;
+    type Payload = Box<dyn Fn() -> usize>;
+    /// Neither `Clone` nor `Default`, so interior nodes get a placeholder.
+    struct Placeholder;
+    impl NodeMetadata<Payload> for Placeholder {
+        fn on_split(_: &Payload) -> Payload {
+            Box::new(|| usize::MAX)
+        }
+        fn on_insert(_: &mut Payload, _: &Payload) {}
+    }
+    let mut tree = QuadTree::<4, _, Payload, Payload, EqualSplit, Placeholder>::with_bucket_size(
+        crate::naive::UNIT_SQUARE,
+        1,
+    );
+    let points = crate::naive::random_points(50, 73);
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, Box::new(move || i), Box::new(move || 2 * i))
+            .unwrap();
+    }
+    assert!(tree.relocate(&points[0], DefaultVector([0.5, 0.5])));
+    assert_eq!(tree.remove(&points[1]).map(|data| data()), Some(2));
+    let mut seen: Vec<_> = tree.iter().map(|(_, m, d)| (m(), d())).collect();
+    seen.sort();
+    assert_eq!(
+        seen,
+        (0..50)
+            .filter(|i| *i != 1)
+            .map(|i| (i, 2 * i))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_node_metadata() {
+    use crate::split::EqualSplit;
+    use crate::vector::DefaultVector;
+    /// Interior nodes carry the total mass of the points below them.
+    struct Mass;
+    impl NodeMetadata<f64> for Mass {
+        fn on_split(leaf_meta: &f64) -> f64 {
+            *leaf_meta
+        }
+        fn on_insert(node_meta: &mut f64, leaf_meta: &f64) {
+            *node_meta += leaf_meta;
+        }
+    }
+    fn check<const D: usize, T: Vectorial, V>(node: &DNode<D, T, f64, V>) -> bool {
+        match node {
+            DNode::Node {
+                metadata, children, ..
+            } => {
+                let total: f64 = node.iter().map(|(_, m, _)| m).sum();
+                *metadata == total && children.iter().all(|c| check(c))
+            }
+            _ => true,
+        }
+    }
+    let mut tree =
+        QuadTree::<4, _, f64, (), EqualSplit, Mass>::with_bucket_size(crate::naive::UNIT_SQUARE, 2);
+    for (i, p) in crate::naive::random_points(200, 79).into_iter().enumerate() {
+        tree.insert(p, (i % 7) as f64, ()).unwrap();
+    }
+    assert_eq!(
+        tree.root.metadata(),
+        Some(&tree.iter().map(|(_, m, _)| m).sum())
+    );
+    assert!(check(&tree.root));
+
+    // Without hooks interior nodes just get the default.
+    let mut plain = crate::naive::unit_tree::<f64, ()>(1);
+    plain.insert(DefaultVector([0.2, 0.2]), 5.0, ()).unwrap();
+    plain.insert(DefaultVector([0.7, 0.7]), 5.0, ()).unwrap();
+    assert_eq!(plain.root.metadata(), Some(&0.0));
+}
+
+#[test]
+fn test_octree_range_query() {
+    use crate::vector::DefaultVector;
+    let (xy, z) = (
+        crate::naive::random_points(1000, 83),
+        crate::naive::random_points(1000, 89),
+    );
+    let points: Vec<_> = xy
+        .iter()
+        .zip(&z)
+        .map(|(a, b)| DefaultVector([a[0], a[1], b[0]]))
+        .collect();
+    let mut tree = OcTree::new((DefaultVector::<3>::zero(), DefaultVector::ones()));
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    assert_eq!(tree.len(), 1000);
+    tree.root.assert_valid();
+    let DNode::Node { cells, .. } = &tree.root else {
+        panic!("the root should have been split");
+    };
+    assert_eq!(
+        (*cells[0b101].min(), *cells[0b101].max()),
+        ([0.5, 0.0, 0.5], [1.0, 0.5, 1.0])
+    );
+
+    let query = Aabb::new(
+        DefaultVector([0.2, 0.1, 0.4]),
+        DefaultVector([0.7, 0.5, 0.9]),
+    );
+    let mut found: Vec<_> = tree.query_area(query).iter().map(|(.., i)| **i).collect();
+    found.sort();
+    let expected: Vec<_> = (0..points.len())
+        .filter(|&i| points[i].within(query))
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn test_octants() {
+    use crate::vector::child_halves;
+    let mut tree = OcTree::with_bucket_size((DefaultVector([-1.0; 3]), DefaultVector::ones()), 1);
+    for i in 0..8 {
+        let p = child_halves::<3>(i).map(|upper| if upper { 0.5 } else { -0.5 });
+        tree.insert(DefaultVector(p), (), i).unwrap();
+    }
+    let DNode::Node { children, .. } = &tree.root else {
+        panic!("the root should have been split");
+    };
+    for (i, child) in children.iter().enumerate() {
+        let DNode::Leaf { points, .. } = &**child else {
+            panic!("every octant should hold a leaf");
+        };
+        assert_eq!(points.iter().map(|(.., i)| *i).collect::<Vec<_>>(), [i]);
+    }
+}
+
+#[test]
+#[should_panic(expected = "has to split into 8 cells, not 4")]
+fn test_mismatched_child_count() {
+    QuadTree::<4, _, (), ()>::new((DefaultVector::<3>::zero(), DefaultVector::ones()));
+}
+
+#[test]
+fn test_duplicate_policies() {
+    let bounds = (DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let spot = DefaultVector([0.25, 0.75]);
+    let build = |policy| {
+        let mut tree =
+            QuadTree::<4, _, (), usize>::with_bucket_size(bounds, 4).with_duplicate_policy(policy);
+        tree.insert(DefaultVector([0.9, 0.1]), (), 1000).unwrap();
+        let results: Vec<_> = (0..100).map(|i| tree.insert(spot, (), i)).collect();
+        (tree, results)
+    };
+
+    let (tree, results) = build(DuplicatePolicy::default());
+    assert!(results.iter().all(|r| *r == Ok(None)));
+    assert_eq!(tree.len(), 101);
+    assert_eq!(tree.query_area((spot, spot)).len(), 100);
+
+    let (tree, results) = build(DuplicatePolicy::Reject);
+    assert_eq!(results[0], Ok(None));
+    assert!(
+        results[1..]
+            .iter()
+            .all(|r| *r == Err(InsertError::Duplicate))
+    );
+    assert_eq!(tree.len(), 2);
+    assert_eq!(*tree.query_area((spot, spot))[0].2, 0);
+
+    let (tree, results) = build(DuplicatePolicy::Replace);
+    assert_eq!(results[0], Ok(None));
+    assert!((1..100).all(|i| results[i] == Ok(Some(i - 1))));
+    assert_eq!(tree.len(), 2);
+    assert_eq!(*tree.query_area((spot, spot))[0].2, 99);
+    tree.root.assert_valid();
+}
+
+#[test]
+fn test_relocate_duplicate_policies() {
+    let (from, to) = (DefaultVector([0.1, 0.2]), DefaultVector([0.7, 0.6]));
+    let build = |policy| {
+        let mut tree = crate::naive::unit_tree::<u32, usize>(1).with_duplicate_policy(policy);
+        tree.insert(from, 1, 1).unwrap();
+        tree.insert(to, 2, 2).unwrap();
+        tree.insert(DefaultVector([0.9, 0.9]), 3, 3).unwrap();
+        tree
+    };
+    let at = |tree: &QuadTree<4, _, u32, usize>, p| {
+        let mut found: Vec<_> = tree.query_area((p, p)).iter().map(|(.., i)| **i).collect();
+        found.sort();
+        found
+    };
+
+    let mut tree = build(DuplicatePolicy::Allow);
+    assert!(tree.relocate(&from, to));
+    assert_eq!((at(&tree, to), tree.len()), (vec![1, 2], 3));
+
+    let mut tree = build(DuplicatePolicy::Reject);
+    let before = tree.root.clone();
+    assert!(!tree.relocate(&from, to));
+    assert_eq!((&tree.root, tree.len()), (&before, 3));
+    // Staying in place isn't a duplicate of itself.
+    assert!(tree.relocate(&from, from));
+
+    let mut tree = build(DuplicatePolicy::Replace);
+    assert!(!tree.relocate(&DefaultVector([0.5, 0.5]), to));
+    assert!(tree.relocate(&from, to));
+    assert_eq!(
+        (at(&tree, to), at(&tree, from), tree.len()),
+        (vec![1], vec![], 2)
+    );
+    assert_eq!(tree.root.find_mut(&to).map(|p| p.1), Some(1));
+    tree.root.assert_valid();
+}
+
+#[test]
+fn test_bulk_build() {
+    use crate::naive::random_points;
+    let bounds = (DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut points: Vec<_> = random_points(500, 61)
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| (p, (), i))
+        .collect();
+    let shape = |tree: &QuadTree<4, DefaultVector<2>, (), usize>| {
+        let levels: Vec<_> = tree
+            .root
+            .iter_levels()
+            .map(|(depth, area, position)| (depth, *area.min(), *area.max(), position.is_some()))
+            .collect();
+        levels
+    };
+    let mut sequential = QuadTree::<4, _, (), usize>::new(bounds);
+    for (p, u, v) in points.clone() {
+        sequential.insert(p, u, v).unwrap();
+    }
+
+    points.push((DefaultVector([1.5, 0.5]), (), 500));
+    points.push((DefaultVector([f64::NAN, 0.5]), (), 501));
+    let (tree, rejects) = QuadTree::<4, _, (), usize>::bulk_build(points.clone(), bounds);
+    assert_eq!(
+        rejects.iter().map(|(.., i)| *i).collect::<Vec<_>>(),
+        [500, 501]
+    );
+    assert_eq!(tree.len(), 500);
+    tree.root.assert_valid();
+    assert_eq!(shape(&tree), shape(&sequential));
+
+    points.reverse();
+    let (reversed, _) = QuadTree::<4, _, (), usize>::bulk_build(points, bounds);
+    assert_eq!(shape(&reversed), shape(&tree));
+}
+
+#[test]
+fn test_bulk_build_morton() {
+    use crate::naive::random_points;
+    let bounds = (DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let points: Vec<_> = random_points(10_000, 67)
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| (p, (), i))
+        .collect();
+    let mut sequential = QuadTree::<4, _, (), usize>::new(bounds);
+    for (p, u, v) in points.clone() {
+        sequential.insert(p, u, v).unwrap();
+    }
+    let (tree, rejects) = QuadTree::<4, _, (), usize>::bulk_build_morton(points.clone(), bounds);
+    assert!(rejects.is_empty());
+    assert_eq!(tree.len(), points.len());
+    tree.root.assert_valid();
+    let found = |tree: &QuadTree<4, DefaultVector<2>, (), usize>| {
+        let mut found: Vec<_> = tree.query_area(bounds).iter().map(|(.., i)| **i).collect();
+        found.sort();
+        found
+    };
+    assert_eq!(found(&tree), found(&sequential));
+    let (plain, _) = QuadTree::<4, _, (), usize>::bulk_build(points, bounds);
+    let levels = |tree: &QuadTree<4, DefaultVector<2>, (), usize>| {
+        let levels: Vec<_> = tree
+            .root
+            .iter_levels()
+            .map(|(depth, area, _)| (depth, *area))
+            .collect();
+        levels
+    };
+    assert_eq!(levels(&tree), levels(&plain));
+
+    let unit = Aabb::from(bounds);
+    assert_eq!(DefaultVector([0.1, 0.1]).morton_code(&unit, 2), 0b0000);
+    assert_eq!(DefaultVector([0.9, 0.1]).morton_code(&unit, 2), 0b0101);
+    assert_eq!(DefaultVector([0.3, 0.8]).morton_code(&unit, 2), 0b1011);
+}
+
+#[test]
+fn test_merge() {
+    use crate::naive::random_points;
+    let unit = (DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut tree = QuadTree::<4, _, (), usize>::with_bucket_size(unit, 2);
+    tree.extend(
+        random_points(100, 71)
+            .into_iter()
+            .zip(0..)
+            .map(|(p, i)| (p, (), i)),
+    );
+    assert_eq!(tree.len(), 100);
+
+    let mut inner = QuadTree::<4, _, (), usize>::with_bucket_size(
+        (DefaultVector([0.25, 0.25]), DefaultVector([0.75, 0.75])),
+        2,
+    );
+    inner.extend([(DefaultVector([0.5, 0.5]), (), 100)]);
+    tree.merge(inner);
+    assert_eq!(tree.len(), 101);
+    assert_eq!(tree.bounds(), unit.into());
+
+    let mut far =
+        QuadTree::<4, _, (), usize>::new((DefaultVector([2.0, -1.0]), DefaultVector([3.0, 0.0])));
+    far.extend([(DefaultVector([3.0, -1.0]), (), 101)]);
+    tree.merge(far);
+    assert_eq!(tree.len(), 102);
+    assert_eq!(
+        tree.bounds(),
+        Aabb::new(DefaultVector([0.0, -1.0]), DefaultVector([3.0, 1.0]))
+    );
+    tree.root.assert_valid();
+    let mut found: Vec<_> = tree.iter().map(|(.., i)| *i).collect();
+    found.sort();
+    assert_eq!(found, (0..102).collect::<Vec<_>>());
+
+    let mut rejecting =
+        QuadTree::<4, _, (), usize>::new(unit).with_duplicate_policy(DuplicatePolicy::Reject);
+    rejecting.extend([
+        (DefaultVector([0.5, 0.5]), (), 0),
+        (DefaultVector([0.5, 0.5]), (), 1),
+    ]);
+    assert_eq!(rejecting.len(), 1);
+}
+
+#[test]
+fn test_clear_and_depth() {
+    use crate::ArenaTree;
+    let bounds = (DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut tree = QuadTree::<4, _, (), ()>::with_bucket_size(bounds, 1);
+    let mut arena = ArenaTree::<4, _, (), ()>::with_bucket_size(bounds, 1);
+    assert_eq!((tree.depth(), arena.depth()), (0, 0));
+    tree.insert(DefaultVector([0.1, 0.1]), (), ()).unwrap();
+    arena.insert(DefaultVector([0.1, 0.1]), (), ()).unwrap();
+    assert_eq!((tree.depth(), arena.depth()), (1, 1));
+    // Splits down to the cell [0, 0.25]^2 to separate the two points.
+    tree.insert(DefaultVector([0.2, 0.2]), (), ()).unwrap();
+    arena.insert(DefaultVector([0.2, 0.2]), (), ()).unwrap();
+    assert_eq!((tree.depth(), arena.depth()), (4, 4));
+
+    let capacity = arena.node_capacity();
+    tree.clear();
+    arena.clear();
+    assert!(tree.is_empty() && arena.is_empty());
+    assert_eq!((tree.depth(), arena.depth()), (0, 0));
+    assert_eq!(tree.bounds(), bounds.into());
+    assert_eq!(tree.bucket_size(), 1);
+    assert_eq!(arena.node_capacity(), capacity);
+    tree.insert(DefaultVector([0.9, 0.9]), (), ()).unwrap();
+    assert_eq!(tree.len(), 1);
+}
+
+#[test]
+fn test_generic_scalars() {
+    let mut floats =
+        QuadTree::<4, _, (), usize>::new((DefaultVector([0.0f32, 0.0]), DefaultVector([8.0, 8.0])));
+    let mut ints =
+        QuadTree::<4, _, (), usize>::new((DefaultVector([-4i64, -4]), DefaultVector([4, 4])));
+    for (i, [x, y]) in [[-1, 0], [0, 0], [1, 0], [3, -4], [-2, 2]]
+        .into_iter()
+        .enumerate()
+    {
+        floats
+            .insert(DefaultVector([x as f32 + 4.0, y as f32 + 4.0]), (), i)
+            .unwrap();
+        ints.insert(DefaultVector([x, y]), (), i).unwrap();
+    }
+    floats.root.assert_valid();
+    ints.root.assert_valid();
+    let found = |mut hits: Vec<usize>| {
+        hits.sort();
+        hits
+    };
+    assert_eq!(
+        found(
+            ints.query_area((DefaultVector([-1, -1]), DefaultVector([0, 2])))
+                .iter()
+                .map(|h| *h.2)
+                .collect()
+        ),
+        [0, 1]
+    );
+    assert_eq!(
+        ints.nearest(DefaultVector([2, -3])),
+        Some((&DefaultVector([3, -4]), &3))
+    );
+    assert_eq!(
+        floats.nearest(DefaultVector([6.0, 1.0])),
+        Some((&DefaultVector([7.0, 0.0]), &3))
+    );
+    assert_eq!(
+        found(
+            ints.within_radius(DefaultVector([0, 0]), 1.0)
+                .iter()
+                .map(|h| *h.1)
+                .collect()
+        ),
+        found(
+            floats
+                .within_radius(DefaultVector([4.0, 4.0]), 1.0)
+                .iter()
+                .map(|h| *h.1)
+                .collect()
+        )
+    );
+    // The box around the circle rounds to whole coordinates.
+    let radius = |r| {
+        found(
+            ints.query_radius(DefaultVector([0, 0]), r)
+                .iter()
+                .map(|h| *h.2)
+                .collect(),
+        )
+    };
+    assert_eq!(radius(1.4), [0, 1, 2]);
+    assert_eq!(radius(5.0), [0, 1, 2, 3, 4]);
+    assert_eq!(ints.root.centroid(), Some(DefaultVector([0, 0])));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    let mut tree =
+        crate::naive::unit_tree::<(), String>(2).with_duplicate_policy(DuplicatePolicy::Reject);
+    for (i, p) in crate::naive::random_points(40, 5).into_iter().enumerate() {
+        tree.insert(p, (), i.to_string()).unwrap();
+    }
+    let json = serde_json::to_string(&tree).unwrap();
+    let back: QuadTree<4, DefaultVector<2>, (), String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.root, tree.root);
+    assert_eq!((back.len(), back.bounds()), (tree.len(), tree.bounds()));
+    assert_eq!(
+        (back.bucket_size(), back.duplicates),
+        (2, DuplicatePolicy::Reject)
+    );
+    let bytes = postcard::to_allocvec(&tree).unwrap();
+    let back: QuadTree<4, DefaultVector<2>, (), String> = postcard::from_bytes(&bytes).unwrap();
+    assert_eq!((back.len(), &back.root), (tree.len(), &tree.root));
+
+    let v: DefaultVector<3, f32> = serde_json::from_str("[1.0, 2.5, -3.0]").unwrap();
+    assert_eq!(v, DefaultVector([1.0, 2.5, -3.0]));
+    assert_eq!(serde_json::to_string(&v).unwrap(), "[1.0,2.5,-3.0]");
+    assert!(serde_json::from_str::<DefaultVector<3>>("[1.0, 2.5]").is_err());
+    // Corners come back normalized whichever way round they were written.
+    let area: Aabb<DefaultVector<2>> =
+        serde_json::from_str(r#"{"min": [4.0, 0.0], "max": [0.0, 4.0]}"#).unwrap();
+    assert_eq!(
+        area,
+        Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([4.0, 4.0]))
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_rejects_invalid_trees() {
+    let mut tree = crate::naive::unit_tree::<(), usize>(1);
+    tree.insert(DefaultVector([0.1, 0.1]), (), 0).unwrap();
+    tree.insert(DefaultVector([0.9, 0.9]), (), 1).unwrap();
+    let json = serde_json::to_value(&tree).unwrap();
+    let load = |json: &serde_json::Value| {
+        serde_json::from_value::<QuadTree<4, DefaultVector<2>, (), usize>>(json.clone())
+            .map(|tree| tree.len())
+            .map_err(|e| e.to_string())
+    };
+    assert_eq!(load(&json), Ok(2));
+    let broken = |edit: fn(&mut serde_json::Value)| {
+        let mut json = json.clone();
+        edit(&mut json);
+        load(&json).unwrap_err()
+    };
+    assert!(broken(|j| j["len"] = 3.into()).starts_with("cached point count is off"));
+    assert!(broken(|j| j["area"]["max"] = [2.0, 2.0].into()).starts_with("child area differs"));
+    assert!(broken(|j| j["limits"]["capacity"] = 0.into()).contains("a positive bucket size"));
+
+    let DNode::Leaf { points, .. } = &mut tree.root[0] else {
+        panic!("the points should sit in separate leaves");
+    };
+    points[0].0 = DefaultVector([0.6, 0.6]);
+    let json = serde_json::to_value(&tree).unwrap();
+    assert!(
+        load(&json)
+            .unwrap_err()
+            .starts_with("point outside of its leaf's area at child path [0]")
+    );
+}
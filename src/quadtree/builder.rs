@@ -0,0 +1,160 @@
+use core::marker::PhantomData;
+
+use super::{DEFAULT_BUCKET_SIZE, DEFAULT_MAX_DEPTH, QuadTree};
+use crate::aabb::Aabb;
+use crate::metadata::{DefaultMetadata, NodeMetadata};
+use crate::split::{EqualSplit, SplitStrategy};
+use crate::vector::Vectorial;
+
+/// Why [`QuadTreeBuilder::build`] refused to build a tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// No area was given with [`QuadTreeBuilder::with_area`].
+    MissingArea,
+    /// A corner of the area has an infinite or `NaN` coordinate.
+    NonFiniteArea,
+    /// The area has no extent along one of the axes the tree splits.
+    DegenerateArea,
+    /// Leaves would split before holding a single point.
+    ZeroCapacity,
+}
+
+impl core::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BuildError::MissingArea => write!(f, "no area to cover was given"),
+            BuildError::NonFiniteArea => write!(f, "area has a non-finite corner"),
+            BuildError::DegenerateArea => write!(f, "area is flat along an axis"),
+            BuildError::ZeroCapacity => write!(f, "leaves must be able to hold a point"),
+        }
+    }
+}
+
+impl core::error::Error for BuildError {}
+
+/// Sets up a [`QuadTree`] one option at a time, checking them all in
+/// [`QuadTreeBuilder::build`] instead of panicking. Starts out with the
+/// defaults of [`QuadTree::new`], only the area has to be given.
+#[derive(Clone, Debug)]
+pub struct QuadTreeBuilder<const D: usize, T: Vectorial, U, V, S = EqualSplit, M = DefaultMetadata>
+{
+    area: Option<Aabb<T>>,
+    capacity: usize,
+    max_depth: usize,
+    strategy: PhantomData<(U, V, S, M)>,
+}
+
+impl<const D: usize, T: Vectorial, U, V> QuadTreeBuilder<D, T, U, V> {
+    pub fn new() -> Self {
+        Self {
+            area: None,
+            capacity: DEFAULT_BUCKET_SIZE,
+            max_depth: DEFAULT_MAX_DEPTH,
+            strategy: PhantomData,
+        }
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V> QuadTree<D, T, U, V> {
+    /// See [`QuadTreeBuilder`].
+    pub fn builder() -> QuadTreeBuilder<D, T, U, V> {
+        QuadTreeBuilder::new()
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V> Default for QuadTreeBuilder<D, T, U, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V, S, M> QuadTreeBuilder<D, T, U, V, S, M> {
+    /// The area the tree covers, from corners in any order.
+    pub fn with_area(mut self, min: T, max: T) -> Self {
+        self.area = Some(Aabb::new(min, max));
+        self
+    }
+
+    /// Points a leaf holds before splitting, see [`QuadTree::with_bucket_size`].
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Levels below the root to split down to, see [`QuadTree::with_limits`].
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Splits overflowing leaves with `strategy` instead.
+    pub fn with_strategy<S2: SplitStrategy<D, T>>(
+        self,
+        _strategy: S2,
+    ) -> QuadTreeBuilder<D, T, U, V, S2, M> {
+        QuadTreeBuilder {
+            area: self.area,
+            capacity: self.capacity,
+            max_depth: self.max_depth,
+            strategy: PhantomData,
+        }
+    }
+}
+
+impl<const D: usize, T: Vectorial, U, V, S: SplitStrategy<D, T>, M: NodeMetadata<U>>
+    QuadTreeBuilder<D, T, U, V, S, M>
+{
+    /// An empty tree with the options given so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `D` doesn't match [`Vectorial::DIMENSION`], like
+    /// [`QuadTree::new`].
+    pub fn build(self) -> Result<QuadTree<D, T, U, V, S, M>, BuildError> {
+        let area = self.area.ok_or(BuildError::MissingArea)?;
+        let (min, max) = (area.min(), area.max());
+        if !(min.is_finite() && max.is_finite()) {
+            return Err(BuildError::NonFiniteArea);
+        }
+        if (0..D.trailing_zeros() as usize).any(|k| min.component(k) == max.component(k)) {
+            return Err(BuildError::DegenerateArea);
+        }
+        if self.capacity == 0 {
+            return Err(BuildError::ZeroCapacity);
+        }
+        Ok(QuadTree::with_limits(area, self.capacity, self.max_depth))
+    }
+}
+
+#[test]
+fn test_builder() {
+    use crate::MidpointSplit;
+    use crate::vector::DefaultVector;
+    let (lo, hi) = (DefaultVector([4.0, 4.0]), DefaultVector([0.0, 0.0]));
+    let tree = QuadTreeBuilder::<4, _, (), ()>::new()
+        .with_area(lo, hi)
+        .with_capacity(2)
+        .with_max_depth(3)
+        .with_strategy(MidpointSplit)
+        .build()
+        .unwrap();
+    assert_eq!(tree.bounds(), Aabb::new(hi, lo));
+    assert_eq!((tree.bucket_size(), tree.max_depth()), (2, 3));
+    let _: QuadTree<4, _, (), (), MidpointSplit> = tree;
+
+    let build = |builder: QuadTreeBuilder<4, DefaultVector<2>, (), ()>| builder.build().err();
+    let builder = QuadTree::builder();
+    assert_eq!(build(builder.clone()), Some(BuildError::MissingArea));
+    let flat = builder.clone().with_area(lo, DefaultVector([0.0, 4.0]));
+    assert_eq!(build(flat), Some(BuildError::DegenerateArea));
+    let infinite = builder
+        .clone()
+        .with_area(lo, DefaultVector([f64::INFINITY, 0.0]));
+    assert_eq!(build(infinite), Some(BuildError::NonFiniteArea));
+    let empty = builder.with_area(lo, hi).with_capacity(0);
+    assert_eq!(build(empty), Some(BuildError::ZeroCapacity));
+    assert_eq!(
+        BuildError::DegenerateArea.to_string(),
+        "area is flat along an axis"
+    );
+}
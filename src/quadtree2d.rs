@@ -0,0 +1,160 @@
+use alloc::vec::Vec;
+
+use crate::aabb::Aabb;
+use crate::metadata::{DefaultMetadata, NodeMetadata};
+use crate::naive::{DNode, InsertError, LeafIter, route};
+use crate::quadtree::QuadTree;
+use crate::split::EqualSplit;
+use crate::vector::Vectorial;
+
+/// Quadrants of a 2-D cell, taking axis 0 to point east and axis 1 north.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quadrant {
+    SouthWest,
+    SouthEast,
+    NorthWest,
+    NorthEast,
+}
+
+impl Quadrant {
+    pub const ALL: [Quadrant; 4] = [
+        Quadrant::SouthWest,
+        Quadrant::SouthEast,
+        Quadrant::NorthWest,
+        Quadrant::NorthEast,
+    ];
+
+    /// Child slot of the quadrant, see [`Vectorial::split_area`].
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The quadrant of `area` a point at `position` is routed to.
+    pub fn of<T: Vectorial>(area: Aabb<T>, position: &T) -> Option<Quadrant> {
+        let cells: [_; 4] = core::array::from_fn(|i| area.split(i));
+        route(&cells, position).map(|i| Self::ALL[i])
+    }
+}
+
+/// Point quadtree over a plane, splitting every overflowing cell into its
+/// four quadrants. `M` is passed on to the underlying [`QuadTree`].
+#[derive(Clone, Debug)]
+pub struct QuadTree2D<T: Vectorial, U, V, M = DefaultMetadata> {
+    tree: QuadTree<4, T, U, V, EqualSplit, M>,
+}
+
+impl<T: Vectorial, U, V, M: NodeMetadata<U>> QuadTree2D<T, U, V, M> {
+    pub fn new(bounds: impl Into<Aabb<T>>) -> Self {
+        Self {
+            tree: QuadTree::new(bounds),
+        }
+    }
+
+    pub fn with_bucket_size(bounds: impl Into<Aabb<T>>, bucket_size: usize) -> Self {
+        Self {
+            tree: QuadTree::with_bucket_size(bounds, bucket_size),
+        }
+    }
+
+    /// See [`QuadTree::insert`].
+    pub fn insert(
+        &mut self,
+        position: impl Into<T>,
+        metadata: U,
+        data: V,
+    ) -> Result<Option<V>, InsertError>
+    where
+        T: PartialEq,
+    {
+        self.tree.insert(position, metadata, data)
+    }
+
+    pub fn remove(&mut self, position: &T) -> Option<V>
+    where
+        T: PartialEq,
+    {
+        self.tree.remove(position)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn bounds(&self) -> Aabb<T> {
+        self.tree.bounds()
+    }
+
+    pub fn clear(&mut self) {
+        self.tree.clear()
+    }
+
+    /// See [`QuadTree::depth`].
+    pub fn depth(&self) -> usize {
+        self.tree.depth()
+    }
+
+    /// Every stored point within the axis-aligned box `area`, boundaries
+    /// included. Quadrants outside of `area` are skipped entirely.
+    pub fn query_area(&self, area: impl Into<Aabb<T>>) -> Vec<(&T, &U, &V)> {
+        self.tree.query_area(area)
+    }
+
+    /// See [`QuadTree::nearest`].
+    pub fn nearest(&self, target: T) -> Option<(&T, &V)> {
+        self.tree.nearest(target)
+    }
+
+    /// Every stored point routed to quadrant `q` of the whole tree.
+    pub fn quadrant(&self, q: Quadrant) -> Vec<(&T, &U, &V)> {
+        match &self.tree.root {
+            DNode::Node { children, .. } => children[q.index()].iter().collect(),
+            root => root
+                .iter()
+                .filter(|(p, ..)| Quadrant::of(self.bounds(), p) == Some(q))
+                .collect(),
+        }
+    }
+
+    pub fn iter(&self) -> LeafIter<'_, 4, T, U, V> {
+        self.tree.iter()
+    }
+}
+
+#[test]
+fn test_quadrants() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([2.0, 2.0]));
+    let mut tree = QuadTree2D::<_, (), Quadrant>::with_bucket_size(area, 1);
+    let corners = [
+        ([0.5, 0.5], Quadrant::SouthWest),
+        ([1.5, 0.5], Quadrant::SouthEast),
+        ([0.5, 1.5], Quadrant::NorthWest),
+        ([1.5, 1.5], Quadrant::NorthEast),
+    ];
+    tree.insert(DefaultVector(corners[0].0), (), corners[0].1)
+        .unwrap();
+    assert_eq!(tree.quadrant(Quadrant::SouthWest).len(), 1);
+    assert!(tree.quadrant(Quadrant::NorthEast).is_empty());
+    for (p, q) in &corners[1..] {
+        tree.insert(DefaultVector(*p), (), *q).unwrap();
+    }
+    for (p, q) in corners {
+        assert_eq!(Quadrant::of(area, &DefaultVector(p)), Some(q));
+        let found = tree.quadrant(q);
+        assert_eq!(found.len(), 1);
+        assert_eq!(*found[0].2, q);
+    }
+    let west = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([0.9, 2.0]));
+    let mut found: Vec<_> = tree.query_area(west).iter().map(|(.., q)| **q).collect();
+    found.sort_by_key(|q| q.index());
+    assert_eq!(found, [Quadrant::SouthWest, Quadrant::NorthWest]);
+    // The centre lies on all split planes and goes to the upper quadrant.
+    assert_eq!(
+        Quadrant::of(area, &DefaultVector([1.0, 1.0])),
+        Some(Quadrant::NorthEast)
+    );
+}
@@ -0,0 +1,416 @@
+/*
+Counterpart of `QuadTree` for items with an extent of their own.
+*/
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::aabb::Aabb;
+use crate::naive::{InsertError, Limits, Ray, ray};
+use crate::quadtree::{DEFAULT_BUCKET_SIZE, DEFAULT_MAX_DEPTH};
+use crate::vector::{Vectorial, check_child_count};
+
+#[derive(Clone, Debug)]
+struct RectNode<const D: usize, T, V> {
+    area: Aabb<T>,
+    /// `area` inflated by the looseness of the tree, what items have to fit.
+    loose: Aabb<T>,
+    /// Items fitting this node but none of its children.
+    items: Vec<(Aabb<T>, V)>,
+    children: Option<Box<[RectNode<D, T, V>; D]>>,
+}
+
+/// Whether `item` lies within `area` as a whole.
+fn fits<T: Vectorial>(item: &Aabb<T>, area: Aabb<T>) -> bool {
+    item.min().within(area) && item.max().within(area)
+}
+
+/// `area` scaled by `factor` about its center.
+fn inflate<T: Vectorial>(area: Aabb<T>, factor: f64) -> Aabb<T> {
+    let center = area.center();
+    Aabb::new(
+        center.lerp(&area.min(), factor),
+        center.lerp(&area.max(), factor),
+    )
+}
+
+impl<const D: usize, T: Vectorial, V> RectNode<D, T, V> {
+    fn new(area: Aabb<T>, loose: Aabb<T>) -> Self {
+        Self {
+            area,
+            loose,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    /// The child whose cell holds the center of `item`, as long as `item`
+    /// fits its loose area. With a looseness of 1 that is the first child
+    /// holding `item` as a whole.
+    fn child_for(&mut self, item: &Aabb<T>) -> Option<&mut Self> {
+        let center = item.center();
+        let children = self.children.as_deref_mut()?;
+        children
+            .iter_mut()
+            .find(|c| center.within(c.area))
+            .filter(|c| fits(item, c.loose))
+    }
+
+    fn insert(&mut self, item: (Aabb<T>, V), limits: Limits, looseness: f64, depth: usize) {
+        if let Some(child) = self.child_for(&item.0) {
+            return child.insert(item, limits, looseness, depth + 1);
+        }
+        self.items.push(item);
+        if self.children.is_none() && self.items.len() > limits.capacity && depth < limits.max_depth
+        {
+            let area = self.area;
+            self.children = Some(Box::new(core::array::from_fn(|i| {
+                let cell = area.split(i);
+                Self::new(cell, inflate(cell, looseness))
+            })));
+            for item in core::mem::take(&mut self.items) {
+                match self.child_for(&item.0) {
+                    Some(child) => child.insert(item, limits, looseness, depth + 1),
+                    None => self.items.push(item),
+                }
+            }
+        }
+    }
+}
+
+/// Tree over items carrying their own box, like sprite or mesh bounds. Every
+/// item sits in the deepest node whose area holds its box as a whole, so one
+/// straddling a split plane stays with the parent instead of being stored
+/// once per cell. Nodes split once they directly hold more than the bucket
+/// size, keeping the straddlers.
+///
+/// Moving items that keep crossing split planes can make the tree loose
+/// instead, see [`RectQuadTree::with_looseness`].
+#[derive(Clone, Debug)]
+pub struct RectQuadTree<const D: usize, T: Vectorial, V> {
+    root: RectNode<D, T, V>,
+    len: usize,
+    limits: Limits,
+    looseness: f64,
+}
+
+impl<const D: usize, T: Vectorial, V> RectQuadTree<D, T, V> {
+    pub fn new(bounds: impl Into<Aabb<T>>) -> Self {
+        Self::with_limits(bounds, DEFAULT_BUCKET_SIZE, DEFAULT_MAX_DEPTH)
+    }
+
+    /// See [`QuadTree::with_limits`](crate::QuadTree::with_limits), items too
+    /// big for any child don't count towards a split.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `bucket_size` is zero or `D` doesn't match
+    /// [`Vectorial::DIMENSION`].
+    pub fn with_limits(bounds: impl Into<Aabb<T>>, bucket_size: usize, max_depth: usize) -> Self {
+        assert!(bucket_size > 0, "nodes must be able to hold an item");
+        check_child_count::<D, T>();
+        let area = bounds.into();
+        Self {
+            root: RectNode::new(area, area),
+            len: 0,
+            limits: Limits {
+                capacity: bucket_size,
+                max_depth,
+            },
+            looseness: 1.0,
+        }
+    }
+
+    /// Lets every child take items reaching past its cell: items go to the
+    /// child holding their center, as long as they fit its cell scaled by
+    /// `factor` about its center. With a factor of 2 an item is stored at
+    /// least as deep as the level whose cells are as large as the item,
+    /// however it lies relative to the split planes; 1 keeps cells strict.
+    /// Queries test against the scaled areas accordingly. Items still have
+    /// to lie within the area of the tree itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `factor` is below 1 or the tree already holds items.
+    pub fn with_looseness(mut self, factor: f64) -> Self {
+        assert!(factor >= 1.0, "looseness must be at least 1, got {factor}");
+        assert!(self.is_empty(), "looseness has to be set before inserting");
+        self.root.children = None;
+        self.looseness = factor;
+        self
+    }
+
+    /// Stores `data` covering `bounds`, which has to lie within the area of
+    /// the tree as a whole.
+    pub fn insert(&mut self, bounds: impl Into<Aabb<T>>, data: V) -> Result<(), InsertError> {
+        let bounds = bounds.into();
+        if !(bounds.min().is_finite() && bounds.max().is_finite()) {
+            return Err(InsertError::NonFinite);
+        }
+        if !fits(&bounds, self.root.area) {
+            return Err(InsertError::OutOfBounds);
+        }
+        self.root
+            .insert((bounds, data), self.limits, self.looseness, 0);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes an item stored with exactly `bounds` and hands back its data.
+    /// Children are kept even once empty.
+    pub fn remove(&mut self, bounds: &Aabb<T>) -> Option<V>
+    where
+        T: PartialEq,
+    {
+        let mut stack = vec![&mut self.root];
+        while let Some(node) = stack.pop() {
+            if let Some(i) = node.items.iter().position(|(b, _)| b == bounds) {
+                self.len -= 1;
+                return Some(node.items.swap_remove(i).1);
+            }
+            if let Some(children) = node.children.as_deref_mut() {
+                stack.extend(children.iter_mut().filter(|c| fits(bounds, c.loose)));
+            }
+        }
+        None
+    }
+
+    /// Every item whose box intersects `area`, touching boundaries included.
+    pub fn query_area(&self, area: impl Into<Aabb<T>>) -> Vec<(&Aabb<T>, &V)> {
+        let area = area.into();
+        let mut found = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            if !T::intersects(node.loose, area) {
+                continue;
+            }
+            for (bounds, data) in &node.items {
+                if T::intersects(*bounds, area) {
+                    found.push((bounds, data));
+                }
+            }
+            stack.extend(node.children.iter().flat_map(|c| c.iter()));
+        }
+        found
+    }
+
+    /// Every stored item crossed by the segment `origin + direction * t` with
+    /// `t` in `0..=max_t`, and the `t` where it enters the item, sorted by
+    /// that. Finds nothing when `max_t` is negative or `NaN`.
+    pub fn query_ray(&self, origin: T, direction: T, max_t: f64) -> Vec<(&Aabb<T>, &V, f64)> {
+        if ray::rejects(max_t, 0.0) {
+            return Vec::new();
+        }
+        let ray = Ray {
+            origin: &origin,
+            direction: &direction,
+            max_t,
+            axes: D.trailing_zeros() as usize,
+        };
+        let mut found = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            if ray.entry(node.loose, 0.0).is_none() {
+                continue;
+            }
+            for (bounds, data) in &node.items {
+                if let Some(t) = ray.entry(*bounds, 0.0) {
+                    found.push((bounds, data, t));
+                }
+            }
+            stack.extend(node.children.iter().flat_map(|c| c.iter()));
+        }
+        found.sort_by(|a, b| a.2.total_cmp(&b.2));
+        found
+    }
+
+    /// Every stored item, parents before their children.
+    pub fn iter(&self) -> impl Iterator<Item = (&Aabb<T>, &V)> {
+        let mut stack = vec![&self.root];
+        core::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().flat_map(|c| c.iter().rev()));
+            Some(node.items.iter().map(|(bounds, data)| (bounds, data)))
+        })
+        .flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The area covered by the tree.
+    pub fn bounds(&self) -> Aabb<T> {
+        self.root.area
+    }
+}
+
+#[test]
+fn test_rect_straddlers_stay_up() {
+    use crate::vector::DefaultVector;
+    let rect = |lo: [f64; 2], hi: [f64; 2]| Aabb::new(DefaultVector(lo), DefaultVector(hi));
+    let mut tree = RectQuadTree::<4, _, char>::with_limits(rect([0.0, 0.0], [8.0, 8.0]), 1, 8);
+    tree.insert(rect([1.0, 1.0], [2.0, 2.0]), 'a').unwrap();
+    // Crosses the vertical split plane at x = 4.
+    tree.insert(rect([3.0, 1.0], [5.0, 2.0]), 'b').unwrap();
+    tree.insert(rect([6.0, 6.0], [7.0, 7.0]), 'c').unwrap();
+    tree.insert(rect([5.0, 5.0], [5.5, 5.5]), 'd').unwrap();
+    assert_eq!(tree.len(), 4);
+    assert_eq!(
+        tree.root.items.iter().map(|i| i.1).collect::<Vec<_>>(),
+        ['b']
+    );
+    let children = tree.root.children.as_deref().unwrap();
+    assert_eq!(
+        children[0].items.iter().map(|i| i.1).collect::<Vec<_>>(),
+        ['a']
+    );
+    // `c` and `d` split the north-east quadrant in turn.
+    assert!(children[3].items.is_empty() && children[3].children.is_some());
+
+    assert_eq!(
+        tree.insert(rect([7.0, 7.0], [9.0, 9.0]), 'e'),
+        Err(InsertError::OutOfBounds)
+    );
+    let infinite = Aabb::new(
+        DefaultVector([f64::INFINITY, 0.0]),
+        DefaultVector([1.0, 1.0]),
+    );
+    assert_eq!(tree.insert(infinite, 'e'), Err(InsertError::NonFinite));
+    let mut found: Vec<_> = tree
+        .query_area(rect([4.0, 0.0], [6.0, 5.0]))
+        .into_iter()
+        .map(|(_, c)| *c)
+        .collect();
+    found.sort();
+    assert_eq!(found, ['b', 'd']);
+    let mut all: Vec<_> = tree.iter().map(|(_, c)| *c).collect();
+    all.sort();
+    assert_eq!(all, ['a', 'b', 'c', 'd']);
+
+    assert_eq!(tree.remove(&rect([3.0, 1.0], [5.0, 2.0])), Some('b'));
+    assert_eq!(tree.remove(&rect([6.0, 6.0], [7.0, 7.0])), Some('c'));
+    assert_eq!(tree.remove(&rect([6.0, 6.0], [7.0, 7.0])), None);
+    assert_eq!(tree.len(), 2);
+}
+
+#[test]
+fn test_rect_query_matches_brute_force() {
+    use crate::vector::DefaultVector;
+    let corners = crate::naive::random_points(400, 5);
+    let items: Vec<_> = corners
+        .chunks(2)
+        .map(|c| Aabb::new(c[0], c[0] + (c[1] - c[0]) * 0.1))
+        .collect();
+    let unit = (DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let mut tree = RectQuadTree::<4, _, usize>::with_limits(unit, 4, 16);
+    for (i, item) in items.iter().enumerate() {
+        tree.insert(*item, i).unwrap();
+    }
+    assert_eq!(tree.iter().count(), items.len());
+    for query in items.iter().step_by(9) {
+        let mut found: Vec<_> = tree
+            .query_area(*query)
+            .into_iter()
+            .map(|(_, i)| *i)
+            .collect();
+        found.sort();
+        let expected: Vec<_> = (0..items.len())
+            .filter(|&i| DefaultVector::intersects(items[i], *query))
+            .collect();
+        assert_eq!(found, expected);
+    }
+}
+
+#[test]
+fn test_loose_rect_tree() {
+    use crate::vector::DefaultVector;
+    let rect = |lo: [f64; 2], hi: [f64; 2]| Aabb::new(DefaultVector(lo), DefaultVector(hi));
+    let area = rect([0.0, 0.0], [8.0, 8.0]);
+    let build = |factor| {
+        let mut tree = RectQuadTree::<4, _, char>::with_limits(area, 1, 8).with_looseness(factor);
+        tree.insert(rect([1.0, 1.0], [2.0, 2.0]), 'a').unwrap();
+        // Crosses the split plane at x = 4 with its center right on it.
+        tree.insert(rect([3.0, 1.0], [5.0, 2.0]), 'b').unwrap();
+        tree
+    };
+    let strict = build(1.0);
+    assert_eq!(strict.root.items.len(), 1);
+    let loose = build(2.0);
+    assert!(loose.root.items.is_empty());
+    let children = loose.root.children.as_deref().unwrap();
+    assert_eq!(children[0].loose, rect([-2.0, -2.0], [6.0, 6.0]));
+    // `b` sinks to the cell holding its center, straddling planes there too.
+    let inner = children[0].children.as_deref().unwrap();
+    assert_eq!(inner[1].area, rect([2.0, 0.0], [4.0, 2.0]));
+    assert_eq!(
+        inner[1].items.iter().map(|i| i.1).collect::<Vec<_>>(),
+        ['b']
+    );
+    // Reaching into the south-east cell, past the strict bounds of `b`'s.
+    let found: Vec<_> = loose
+        .query_area(rect([4.5, 0.0], [5.0, 3.0]))
+        .into_iter()
+        .map(|(_, c)| *c)
+        .collect();
+    assert_eq!(found, ['b']);
+
+    let corners = crate::naive::random_points(400, 8);
+    let items: Vec<_> = corners
+        .chunks(2)
+        .map(|c| Aabb::new(c[0], c[0] + (c[1] - c[0]) * 0.2))
+        .collect();
+    let unit = rect([0.0, 0.0], [1.0, 1.0]);
+    let mut tree = RectQuadTree::<4, _, usize>::with_limits(unit, 2, 16).with_looseness(2.0);
+    for (i, item) in items.iter().enumerate() {
+        tree.insert(*item, i).unwrap();
+    }
+    for query in items.iter().step_by(7) {
+        let mut found: Vec<_> = tree
+            .query_area(*query)
+            .into_iter()
+            .map(|(_, i)| *i)
+            .collect();
+        found.sort();
+        let expected: Vec<_> = (0..items.len())
+            .filter(|&i| DefaultVector::intersects(items[i], *query))
+            .collect();
+        assert_eq!(found, expected);
+    }
+    for (i, item) in items.iter().enumerate() {
+        assert_eq!(tree.remove(item), Some(i));
+    }
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn test_rect_query_ray() {
+    use crate::vector::DefaultVector;
+    let rect = |lo: [f64; 2], hi: [f64; 2]| Aabb::new(DefaultVector(lo), DefaultVector(hi));
+    let mut tree = RectQuadTree::<4, _, char>::with_limits(rect([0.0, 0.0], [8.0, 8.0]), 1, 8);
+    tree.insert(rect([1.0, 1.0], [2.0, 2.0]), 'a').unwrap();
+    tree.insert(rect([5.0, 0.5], [6.0, 3.0]), 'b').unwrap();
+    tree.insert(rect([3.0, 1.0], [5.0, 2.0]), 'c').unwrap();
+    tree.insert(rect([6.0, 6.0], [7.0, 7.0]), 'd').unwrap();
+    let hits = |origin, direction, max_t| -> Vec<_> {
+        tree.query_ray(DefaultVector(origin), DefaultVector(direction), max_t)
+            .into_iter()
+            .map(|(_, c, t)| (*c, t))
+            .collect()
+    };
+    // Straight along y = 1.5, never dividing by the zero component.
+    assert_eq!(
+        hits([0.0, 1.5], [2.0, 0.0], 10.0),
+        [('a', 0.5), ('c', 1.5), ('b', 2.5)]
+    );
+    assert_eq!(hits([0.0, 1.5], [2.0, 0.0], 2.0), [('a', 0.5), ('c', 1.5)]);
+    // Starting inside an item enters it at once.
+    assert_eq!(hits([6.5, 6.5], [1.0, 1.0], 1.0), [('d', 0.0)]);
+    assert!(hits([0.0, 4.0], [1.0, 0.0], 8.0).is_empty());
+    assert!(hits([0.0, 1.5], [2.0, 0.0], -1.0).is_empty());
+    assert!(hits([0.0, 1.5], [2.0, 0.0], f64::NAN).is_empty());
+}
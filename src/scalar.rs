@@ -0,0 +1,108 @@
+use core::fmt::Debug;
+use core::ops::{Add, Div, Mul, Sub};
+
+/// Coordinate type of a [`Vectorial`](crate::Vectorial). Distances, norms
+/// and fractions like the `t` of [`Vectorial::lerp`](crate::Vectorial::lerp)
+/// are `f64` whatever the coordinates, converted through [`Scalar::to_f64`],
+/// so squaring integer coordinates can't overflow.
+pub trait Scalar:
+    Copy
+    + PartialOrd
+    + Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    fn to_f64(self) -> f64;
+    /// Rounded to the nearest value for integers, halves upwards so the
+    /// center of `[-1, 0]` is `0` like that of `[0, 1]` is `1`, saturating
+    /// at their bounds.
+    fn from_f64(value: f64) -> Self;
+    fn is_finite(self) -> bool;
+    /// The smaller of both, ignoring a `NaN`.
+    fn min(self, other: Self) -> Self;
+    /// The larger of both, ignoring a `NaN`.
+    fn max(self, other: Self) -> Self;
+    /// Bit pattern to hash by, the same for values that compare equal, so
+    /// `-0.0` maps to the bits of `0.0`.
+    fn key_bits(self) -> u64;
+}
+
+macro_rules! float_scalar {
+    ($($t:ty),*) => {$(
+        impl Scalar for $t {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+            fn is_finite(self) -> bool {
+                <$t>::is_finite(self)
+            }
+            fn min(self, other: Self) -> Self {
+                <$t>::min(self, other)
+            }
+            fn max(self, other: Self) -> Self {
+                <$t>::max(self, other)
+            }
+            fn key_bits(self) -> u64 {
+                if self == 0.0 { 0 } else { self.to_bits().into() }
+            }
+        }
+    )*};
+}
+
+macro_rules! int_scalar {
+    ($($t:ty),*) => {$(
+        impl Scalar for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn from_f64(value: f64) -> Self {
+                // Truncates, then steps down below zero, as `floor` needs std.
+                let value = value + 0.5;
+                let rounded = value as $t;
+                if rounded as f64 > value {
+                    rounded.saturating_sub(1)
+                } else {
+                    rounded
+                }
+            }
+            fn is_finite(self) -> bool {
+                true
+            }
+            fn min(self, other: Self) -> Self {
+                Ord::min(self, other)
+            }
+            fn max(self, other: Self) -> Self {
+                Ord::max(self, other)
+            }
+            fn key_bits(self) -> u64 {
+                self as u64
+            }
+        }
+    )*};
+}
+
+float_scalar!(f32, f64);
+int_scalar!(i32, i64);
+
+#[test]
+fn test_scalar() {
+    assert_eq!(i64::from_f64(2.5), 3);
+    assert_eq!(i64::from_f64(-0.5), 0);
+    assert_eq!(i64::from_f64(-1.7), -2);
+    assert_eq!(i32::from_f64(-1e12), i32::MIN);
+    assert_eq!(i32::from_f64(1e12), i32::MAX);
+    assert_eq!(f32::from_f64(0.5).to_f64(), 0.5);
+    assert!(!f64::NAN.is_finite() && Scalar::is_finite(7i64));
+    assert_eq!(Scalar::min(1.0, f64::NAN), 1.0);
+}
@@ -0,0 +1,58 @@
+/*
+`#[serde(with)]` helpers for arrays of a const generic length, which serde only
+covers for concrete lengths up to 32.
+*/
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeTuple, Serializer};
+
+/// Writes the elements as a tuple, like serde does for short arrays.
+pub(crate) fn serialize<S, X, const N: usize>(
+    array: &[X; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    X: Serialize,
+{
+    let mut tuple = serializer.serialize_tuple(N)?;
+    for x in array {
+        tuple.serialize_element(x)?;
+    }
+    tuple.end()
+}
+
+pub(crate) fn deserialize<'de, D, X, const N: usize>(deserializer: D) -> Result<[X; N], D::Error>
+where
+    D: Deserializer<'de>,
+    X: Deserialize<'de>,
+{
+    deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+}
+
+struct ArrayVisitor<X, const N: usize>(PhantomData<X>);
+
+impl<'de, X: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<X, N> {
+    type Value = [X; N];
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an array of length {N}")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[X; N], A::Error> {
+        let mut items = Vec::with_capacity(N);
+        while let Some(x) = seq.next_element()? {
+            if items.len() == N {
+                return Err(de::Error::invalid_length(N + 1, &self));
+            }
+            items.push(x);
+        }
+        let len = items.len();
+        items
+            .try_into()
+            .map_err(|_| de::Error::invalid_length(len, &self))
+    }
+}
@@ -0,0 +1,63 @@
+use crate::aabb::Aabb;
+use crate::vector::Vectorial;
+
+/// Decides how a leaf covering an area is split into `D` child cells once it
+/// overflows. The cells have to tile the area, with cell `i` holding the
+/// points [`Vectorial::split_area`] would route to it.
+pub trait SplitStrategy<const D: usize, T: Vectorial> {
+    /// Cells of `area`, given the positions of the points it holds (never empty).
+    fn split(area: &Aabb<T>, positions: &[T]) -> [Aabb<T>; D];
+}
+
+/// Bisects every axis, so cells only depend on the area being split.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EqualSplit;
+
+impl<const D: usize, T: Vectorial> SplitStrategy<D, T> for EqualSplit {
+    fn split(area: &Aabb<T>, _: &[T]) -> [Aabb<T>; D] {
+        core::array::from_fn(|i| area.split(i))
+    }
+}
+
+/// Splits at the centroid of the points being split, adapting the cells to
+/// clustered data.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MidpointSplit;
+
+impl<const D: usize, T: Vectorial> SplitStrategy<D, T> for MidpointSplit {
+    fn split(area: &Aabb<T>, positions: &[T]) -> [Aabb<T>; D] {
+        // Rounding may push the centroid just past the extremes.
+        let centroid = crate::vector::mean(positions, D.trailing_zeros() as usize)
+            .expect("leaves being split hold points")
+            .max(area.min())
+            .min(area.max());
+        core::array::from_fn(|i| T::split_area_at(*area, centroid, i))
+    }
+}
+
+#[test]
+fn test_midpoint_split() {
+    use crate::vector::DefaultVector;
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let positions = [DefaultVector([0.1, 0.2]), DefaultVector([0.3, 0.0])];
+    let cells: [_; 4] = MidpointSplit::split(&area, &positions);
+    let corners: Vec<_> = cells.iter().map(|c| (*c.min(), *c.max())).collect();
+    assert_eq!(
+        corners,
+        [
+            ([0.0, 0.0], [0.2, 0.1]),
+            ([0.2, 0.0], [1.0, 0.1]),
+            ([0.0, 0.1], [0.2, 1.0]),
+            ([0.2, 0.1], [1.0, 1.0]),
+        ]
+    );
+    let equal: [_; 4] = EqualSplit::split(&area, &positions);
+    assert_eq!(*equal[3].min(), [0.5, 0.5]);
+
+    // Integer centroids aren't rounded down to the origin.
+    use crate::vector::IntVector;
+    let area = Aabb::new(IntVector([0, 0]), IntVector([8, 8]));
+    let positions = [IntVector([1, 2]), IntVector([2, 4]), IntVector([4, 4])];
+    let cells: [_; 4] = MidpointSplit::split(&area, &positions);
+    assert_eq!(cells[3].min(), IntVector([2, 3]));
+}
@@ -1,49 +1,642 @@
-use std::ops::{Add, Deref, Mul};
+use core::hash::{Hash, Hasher};
+use core::ops::{Add, AddAssign, Deref, Div, Index, IndexMut, Mul, Neg, Sub, SubAssign};
 
-pub trait Vectorial: Sized + Add<Output = Self> + Mul<f64, Output = Self> + Clone + Copy {
-    fn within(&self, _: (Self, Self)) -> bool; // REVIEW should this be part of the interface?
-    fn quarter(&self, _: (Self, Self)) -> (Self, Self);
+use crate::aabb::Aabb;
+use crate::scalar::Scalar;
+
+#[cfg(feature = "cgmath")]
+mod cgmath;
+#[cfg(feature = "geo")]
+mod geo;
+#[cfg(feature = "glam")]
+mod glam;
+mod int;
+#[cfg(feature = "mint")]
+mod mint;
+#[cfg(feature = "nalgebra")]
+mod nalgebra;
+
+#[cfg(feature = "cgmath")]
+pub use cgmath::{CgPoint2, CgPoint3};
+pub use int::IntVector;
+
+/// Positions a tree can store. Besides [`DefaultVector`] and [`IntVector`],
+/// the `nalgebra`, `glam`, `cgmath` and `geo` features implement it for those
+/// crates' vectors. The features are additive: each only adds impls for its own
+/// types, so any of them can be enabled together and trees over different
+/// libraries' vectors live side by side. Moving positions between libraries
+/// goes through [`DefaultVector`]'s `From` impls, which the `mint` feature
+/// extends to mint's types, or plain arrays.
+pub trait Vectorial:
+    Sized
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<<Self as Vectorial>::Scalar, Output = Self>
+    + Clone
+    + Copy
+{
+    /// Type of the coordinates, see [`Scalar`] for how distances are measured.
+    type Scalar: Scalar;
+    /// Number of axes if known, trees over `Self` then have to split their
+    /// areas into `2^DIMENSION` cells.
+    const DIMENSION: Option<usize> = None;
+    fn within(&self, _: Aabb<Self>) -> bool; // REVIEW should this be part of the interface?
+    /// Bounds of the `i`-th child cell of `area`: bit `k` of `i` selects the
+    /// lower or upper half along axis `k`, split at the midpoint.
+    /// Neighbouring cells share their split plane, `within` both of them.
+    /// Insertion routes a point lying on it to the upper cell.
+    fn split_area(area: Aabb<Self>, i: usize) -> Aabb<Self> {
+        Self::split_area_at(area, area.center(), i)
+    }
+    /// Like [`Vectorial::split_area`], splitting at `at` instead of the
+    /// midpoint. `at` is expected to lie within `area`.
+    fn split_area_at(area: Aabb<Self>, at: Self, i: usize) -> Aabb<Self>;
+    /// Whether the two areas overlap, touching boundaries included.
+    fn intersects(a: Aabb<Self>, b: Aabb<Self>) -> bool;
+    fn distance_squared(&self, other: &Self) -> f64;
+    /// Whether no coordinate is infinite or `NaN`. Derived from the distance
+    /// of `self` to itself, which is only zero for finite coordinates.
+    fn is_finite(&self) -> bool {
+        self.distance_squared(self) == 0.0
+    }
+    /// Squared distance to the closest point of `area`, zero when inside.
+    fn min_distance_squared_to_area(&self, area: Aabb<Self>) -> f64;
+    /// The point a fraction `t` of the way from `self` to `other`. Scales by
+    /// `t` converted to the scalar type, which only suits floating point
+    /// coordinates; others should interpolate each component in `f64`.
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        *self * Self::Scalar::from_f64(1.0 - t) + *other * Self::Scalar::from_f64(t)
+    }
+    /// Squared length, the squared distance from the origin.
+    fn norm_squared(&self) -> f64 {
+        self.distance_squared(&Self::zero())
+    }
+    /// Derived from magnitudes via the polarization identity, implementors
+    /// with direct access to their components should compute it directly.
+    fn dot(&self, other: &Self) -> f64 {
+        ((*self + *other).norm_squared() - self.norm_squared() - other.norm_squared()) / 2.0
+    }
+    /// The origin, every component zero.
+    fn zero() -> Self;
+    /// Every component one.
+    fn ones() -> Self;
+    /// Component-wise minimum.
+    fn min(self, other: Self) -> Self;
+    /// Component-wise maximum.
+    fn max(self, other: Self) -> Self;
+    /// Value along `axis`, panics if there is no such axis.
+    fn component(&self, axis: usize) -> Self::Scalar;
+    /// Copy of `self` with the value along `axis` replaced by `val`, panics
+    /// if there is no such axis.
+    fn with_component(&self, axis: usize, val: Self::Scalar) -> Self;
+}
+
+/// `x * x`, as `powi` isn't available without std.
+pub(crate) fn square(x: f64) -> f64 {
+    x * x
+}
+
+/// Which half of every axis child cell `i` of an area covers, `true` for the
+/// upper one: bit `k` of `i` selects the half along axis `k`.
+pub fn child_halves<const N: usize>(i: usize) -> [bool; N] {
+    core::array::from_fn(|k| i >> k & 1 == 1)
 }
 
+/// Mean of `positions` along their first `axes` axes, `None` without any.
+/// Sums are taken in `f64` and converted back once per axis, scaling the sum
+/// by `1 / len` would round to zero for integer scalars.
+pub(crate) fn mean<'a, T: Vectorial + 'a>(
+    positions: impl IntoIterator<Item = &'a T>,
+    axes: usize,
+) -> Option<T> {
+    let mut sums = alloc::vec![0.0; axes];
+    let mut len = 0;
+    for p in positions {
+        for (k, sum) in sums.iter_mut().enumerate() {
+            *sum += p.component(k).to_f64();
+        }
+        len += 1;
+    }
+    (len > 0).then(|| {
+        (sums.into_iter().enumerate()).fold(T::zero(), |mean, (k, sum)| {
+            mean.with_component(k, T::Scalar::from_f64(sum / len as f64))
+        })
+    })
+}
+
+/// Panics unless trees splitting into `D` cells fit `T`, see
+/// [`Vectorial::DIMENSION`].
+pub(crate) fn check_child_count<const D: usize, T: Vectorial>() {
+    if let Some(n) = T::DIMENSION {
+        assert!(
+            n < usize::BITS as usize && D == 1 << n,
+            "a tree over {n} axes has to split into {} cells, not {D}",
+            1u128 << n
+        );
+    }
+}
+
+/// Plain `N`-dimensional point with coordinates of type `S`. Tuples can't
+/// implement [`Vectorial`] as std's `Add` for `(f64, f64)` is out of reach,
+/// convert them with `From` instead.
 #[derive(Clone, Copy, Debug)]
-pub struct DefaultVector<const N: usize>(pub [f64; N]);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct DefaultVector<const N: usize, S = f64>(
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde_array",
+            bound(
+                serialize = "S: serde::Serialize",
+                deserialize = "S: serde::Deserialize<'de>"
+            )
+        )
+    )]
+    pub [S; N],
+);
+
+/// Compares the [`Scalar::key_bits`] of each component, the same bits
+/// [`Hash`] uses. `0.0` and `-0.0` are equal as usual, but unlike with float
+/// comparison a `NaN` component equals itself, so keys holding one can be
+/// found again. `NaN`s with different bit patterns still differ.
+impl<const N: usize, S: Scalar> PartialEq for DefaultVector<N, S> {
+    fn eq(&self, other: &Self) -> bool {
+        (0..N).all(|k| self[k].key_bits() == other[k].key_bits())
+    }
+}
+
+impl<const N: usize, S: Scalar> Eq for DefaultVector<N, S> {}
 
-impl<const N: usize> Deref for DefaultVector<N> {
-    type Target = [f64; N];
+/// Hashes the [`Scalar::key_bits`] of each component.
+impl<const N: usize, S: Scalar> Hash for DefaultVector<N, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for x in self.0 {
+            state.write_u64(x.key_bits());
+        }
+    }
+}
+
+impl<const N: usize, S> Deref for DefaultVector<N, S> {
+    type Target = [S; N];
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl<const N: usize> Add for DefaultVector<N> {
+impl<const N: usize, S: Scalar> Default for DefaultVector<N, S> {
+    /// The origin.
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<const N: usize, S> Index<usize> for DefaultVector<N, S> {
+    type Output = S;
+    fn index(&self, axis: usize) -> &S {
+        &self.0[axis]
+    }
+}
+
+impl<const N: usize, S> IndexMut<usize> for DefaultVector<N, S> {
+    fn index_mut(&mut self, axis: usize) -> &mut S {
+        &mut self.0[axis]
+    }
+}
+
+impl<const N: usize, S: Scalar> Add for DefaultVector<N, S> {
     type Output = Self;
     fn add(self, rhs: Self) -> Self::Output {
-        Self(std::array::from_fn(|i| self[i] + rhs[i]))
+        Self(core::array::from_fn(|i| self[i] + rhs[i]))
+    }
+}
+
+impl<const N: usize, S: Scalar> Sub for DefaultVector<N, S> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(core::array::from_fn(|i| self[i] - rhs[i]))
     }
 }
 
-impl<const N: usize> Mul<f64> for DefaultVector<N> {
+impl<const N: usize, S: Scalar> Mul<S> for DefaultVector<N, S> {
     type Output = Self;
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self(std::array::from_fn(|i| self[i] * rhs))
+    fn mul(self, rhs: S) -> Self::Output {
+        Self(core::array::from_fn(|i| self[i] * rhs))
+    }
+}
+
+impl<const N: usize, S: Scalar> Div<S> for DefaultVector<N, S> {
+    type Output = Self;
+    fn div(self, rhs: S) -> Self::Output {
+        Self(core::array::from_fn(|i| self[i] / rhs))
+    }
+}
+
+impl<const N: usize, S: Scalar + Neg<Output = S>> Neg for DefaultVector<N, S> {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self(core::array::from_fn(|i| -self[i]))
+    }
+}
+
+impl<const N: usize, S: Scalar> AddAssign for DefaultVector<N, S> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const N: usize, S: Scalar> SubAssign for DefaultVector<N, S> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<const N: usize, S> From<[S; N]> for DefaultVector<N, S> {
+    fn from(coords: [S; N]) -> Self {
+        Self(coords)
+    }
+}
+
+impl<const N: usize, S> From<DefaultVector<N, S>> for [S; N] {
+    fn from(v: DefaultVector<N, S>) -> Self {
+        v.0
+    }
+}
+
+impl<S> From<(S, S)> for DefaultVector<2, S> {
+    fn from((x, y): (S, S)) -> Self {
+        Self([x, y])
+    }
+}
+
+impl<S> From<(S, S, S)> for DefaultVector<3, S> {
+    fn from((x, y, z): (S, S, S)) -> Self {
+        Self([x, y, z])
+    }
+}
+
+impl<S: Copy> From<DefaultVector<2, S>> for (S, S) {
+    fn from(v: DefaultVector<2, S>) -> Self {
+        (v[0], v[1])
+    }
+}
+
+impl<S: Copy> From<DefaultVector<3, S>> for (S, S, S) {
+    fn from(v: DefaultVector<3, S>) -> Self {
+        (v[0], v[1], v[2])
+    }
+}
+
+/// Plain arrays and tuples can't be [`Vectorial`] themselves, as the orphan
+/// rule keeps us from implementing `Add` and `Mul` for them. Areas take
+/// their corners in those forms directly instead, in any order, like
+/// positions where a tree asks for `impl Into<T>`.
+impl<const N: usize, S: Scalar> From<([S; N], [S; N])> for Aabb<DefaultVector<N, S>> {
+    fn from((a, b): ([S; N], [S; N])) -> Self {
+        Aabb::new(DefaultVector(a), DefaultVector(b))
+    }
+}
+
+impl<S: Scalar> From<((S, S), (S, S))> for Aabb<DefaultVector<2, S>> {
+    fn from((a, b): ((S, S), (S, S))) -> Self {
+        Aabb::new(a.into(), b.into())
+    }
+}
+
+impl<S: Scalar> From<((S, S, S), (S, S, S))> for Aabb<DefaultVector<3, S>> {
+    fn from((a, b): ((S, S, S), (S, S, S))) -> Self {
+        Aabb::new(a.into(), b.into())
+    }
+}
+
+impl<const N: usize, S> DefaultVector<N, S> {
+    pub const fn new(coords: [S; N]) -> Self {
+        Self(coords)
+    }
+
+    pub const fn as_array(&self) -> &[S; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize, S: Scalar> DefaultVector<N, S> {
+    pub const fn zero() -> Self {
+        Self([S::ZERO; N])
+    }
+
+    pub const fn ones() -> Self {
+        Self([S::ONE; N])
+    }
+
+    /// Z-order code of the cell holding `self` after halving `bounds` along
+    /// every axis `bits_per_axis` times. Each level adds the index of the
+    /// child cell a tree with [`EqualSplit`](crate::EqualSplit) routes the
+    /// point to, the first level in the highest bits, so sorting by code
+    /// orders points by cell on every one of those levels. Positions outside
+    /// `bounds` get the code of the closest cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the code takes more than 64 bits.
+    pub fn morton_code(&self, bounds: &Aabb<Self>, bits_per_axis: u32) -> u64 {
+        assert!(
+            N * bits_per_axis as usize <= 64,
+            "{bits_per_axis} bits for each of {N} axes don't fit a u64"
+        );
+        let (mut area, mut code) = (*bounds, 0u64);
+        for _ in 0..bits_per_axis {
+            let center = area.center();
+            let i = (0..N)
+                .filter(|&k| self[k] >= center[k])
+                .fold(0, |i, k| i | 1 << k);
+            code = code.checked_shl(N as u32).unwrap_or(0) | i as u64;
+            area = area.split(i);
+        }
+        code
     }
 }
 
-impl<const N: usize> Vectorial for DefaultVector<N> {
-    fn within(&self, area: (Self, Self)) -> bool {
-        for i in 0..N {
-            if !(area.0 .0[i].min(area.1 .0[i]) <= self.0[i]
-                && self.0[i] <= area.0 .0[i].max(area.1 .0[i]))
-            {
-                return false;
+impl<const N: usize, S: Scalar> Vectorial for DefaultVector<N, S> {
+    type Scalar = S;
+    const DIMENSION: Option<usize> = Some(N);
+
+    fn within(&self, area: Aabb<Self>) -> bool {
+        let (min, max) = (area.min(), area.max());
+        (0..N).all(|k| min[k] <= self[k] && self[k] <= max[k])
+    }
+
+    fn split_area_at(area: Aabb<Self>, at: Self, i: usize) -> Aabb<Self> {
+        let (mut lo, mut hi) = (area.min(), area.max());
+        for (k, upper) in child_halves::<N>(i).into_iter().enumerate() {
+            if upper {
+                lo = lo.with_component(k, at.component(k));
+            } else {
+                hi = hi.with_component(k, at.component(k));
             }
         }
-        true
+        Aabb::new(lo, hi)
+    }
+
+    fn intersects(a: Aabb<Self>, b: Aabb<Self>) -> bool {
+        (0..N).all(|k| a.min()[k].max(b.min()[k]) <= a.max()[k].min(b.max()[k]))
+    }
+
+    fn distance_squared(&self, other: &Self) -> f64 {
+        (0..N).map(|k| square((self[k] - other[k]).to_f64())).sum()
+    }
+
+    fn is_finite(&self) -> bool {
+        self.iter().all(|x| x.is_finite())
+    }
+
+    fn min_distance_squared_to_area(&self, area: Aabb<Self>) -> f64 {
+        (0..N)
+            .map(|k| {
+                let nearest = self[k].max(area.min()[k]).min(area.max()[k]);
+                square((self[k] - nearest).to_f64())
+            })
+            .sum()
+    }
+
+    /// Interpolates each component in `f64`, so integer coordinates are
+    /// rounded once instead of scaled by a rounded `t`.
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        Self(core::array::from_fn(|k| {
+            S::from_f64(self[k].to_f64() * (1.0 - t) + other[k].to_f64() * t)
+        }))
+    }
+
+    fn norm_squared(&self) -> f64 {
+        self.dot(self)
+    }
+
+    fn dot(&self, other: &Self) -> f64 {
+        (0..N).map(|k| self[k].to_f64() * other[k].to_f64()).sum()
+    }
+
+    fn zero() -> Self {
+        Self::zero()
     }
 
-    fn quarter(&self, _: (Self, Self)) -> (Self, Self) {
-        todo!()
+    fn ones() -> Self {
+        Self::ones()
     }
+
+    fn min(self, other: Self) -> Self {
+        Self(core::array::from_fn(|k| self[k].min(other[k])))
+    }
+
+    fn max(self, other: Self) -> Self {
+        Self(core::array::from_fn(|k| self[k].max(other[k])))
+    }
+
+    fn component(&self, axis: usize) -> S {
+        self[axis]
+    }
+
+    fn with_component(&self, axis: usize, val: S) -> Self {
+        let mut v = *self;
+        v.0[axis] = val;
+        v
+    }
+}
+
+#[test]
+fn test_split_area() {
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([2.0, 4.0]));
+    let cell = DefaultVector::split_area(area, 0b10);
+    assert_eq!((*cell.min(), *cell.max()), ([0.0, 2.0], [1.0, 4.0]));
+}
+
+#[test]
+fn test_split_area_tiles_parent() {
+    let area = Aabb::new(DefaultVector([-1.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let cells: Vec<_> = (0..4).map(|i| DefaultVector::split_area(area, i)).collect();
+    let corners: Vec<_> = cells.iter().map(|c| (*c.min(), *c.max())).collect();
+    assert_eq!(
+        corners,
+        [
+            ([-1.0, 0.0], [0.0, 0.5]),
+            ([0.0, 0.0], [1.0, 0.5]),
+            ([-1.0, 0.5], [0.0, 1.0]),
+            ([0.0, 0.5], [1.0, 1.0]),
+        ]
+    );
+    let on_plane = DefaultVector([0.0, 0.5]);
+    let first = cells.iter().position(|c| on_plane.within(*c));
+    assert_eq!(first, Some(0));
+}
+
+#[test]
+fn test_child_halves() {
+    assert_eq!(child_halves::<3>(0b110), [false, true, true]);
+    assert_eq!(child_halves::<2>(0), [false, false]);
+    check_child_count::<8, DefaultVector<3>>();
+    check_child_count::<4, DefaultVector<2>>();
+    let mismatched = std::panic::catch_unwind(check_child_count::<4, DefaultVector<3>>);
+    assert!(mismatched.is_err());
+}
+
+#[test]
+fn test_intersects() {
+    let a = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    let b = Aabb::new(DefaultVector([2.0, 0.5]), DefaultVector([0.5, 3.0]));
+    let c = Aabb::new(DefaultVector([1.5, 0.0]), DefaultVector([2.0, 1.0]));
+    assert!(DefaultVector::intersects(a, b));
+    assert!(!DefaultVector::intersects(a, c));
+}
+
+#[test]
+fn test_distances() {
+    let p = DefaultVector([3.0, 0.5]);
+    let area = Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0]));
+    assert_eq!(p.distance_squared(&DefaultVector([0.0, 4.5])), 25.0);
+    assert_eq!(*(p - DefaultVector([1.0, 1.0])), [2.0, -0.5]);
+    assert_eq!(p.min_distance_squared_to_area(area), 4.0);
+    assert_eq!(
+        DefaultVector([0.5, 0.5]).min_distance_squared_to_area(area),
+        0.0
+    );
+}
+
+#[test]
+fn test_dot() {
+    /// Only implements what's required, checking the provided methods.
+    #[derive(Clone, Copy)]
+    struct Bare(DefaultVector<2>);
+    impl Add for Bare {
+        type Output = Self;
+        fn add(self, rhs: Self) -> Self {
+            Bare(self.0 + rhs.0)
+        }
+    }
+    impl Sub for Bare {
+        type Output = Self;
+        fn sub(self, rhs: Self) -> Self {
+            Bare(self.0 - rhs.0)
+        }
+    }
+    impl Mul<f64> for Bare {
+        type Output = Self;
+        fn mul(self, rhs: f64) -> Self {
+            Bare(self.0 * rhs)
+        }
+    }
+    fn inner(area: Aabb<Bare>) -> Aabb<DefaultVector<2>> {
+        Aabb::new(area.min().0, area.max().0)
+    }
+    impl Vectorial for Bare {
+        type Scalar = f64;
+        fn within(&self, area: Aabb<Self>) -> bool {
+            self.0.within(inner(area))
+        }
+        fn split_area_at(area: Aabb<Self>, at: Self, i: usize) -> Aabb<Self> {
+            let cell = DefaultVector::split_area_at(inner(area), at.0, i);
+            Aabb::new(Bare(cell.min()), Bare(cell.max()))
+        }
+        fn intersects(a: Aabb<Self>, b: Aabb<Self>) -> bool {
+            DefaultVector::intersects(inner(a), inner(b))
+        }
+        fn distance_squared(&self, other: &Self) -> f64 {
+            self.0.distance_squared(&other.0)
+        }
+        fn min_distance_squared_to_area(&self, area: Aabb<Self>) -> f64 {
+            self.0.min_distance_squared_to_area(inner(area))
+        }
+        fn zero() -> Self {
+            Bare(DefaultVector::zero())
+        }
+        fn ones() -> Self {
+            Bare(DefaultVector::ones())
+        }
+        fn min(self, other: Self) -> Self {
+            Bare(self.0.min(other.0))
+        }
+        fn max(self, other: Self) -> Self {
+            Bare(self.0.max(other.0))
+        }
+        fn component(&self, axis: usize) -> f64 {
+            self.0.component(axis)
+        }
+        fn with_component(&self, axis: usize, val: f64) -> Self {
+            Bare(self.0.with_component(axis, val))
+        }
+    }
+
+    let (p, q) = (DefaultVector([3.0, 4.0]), DefaultVector([-2.0, 0.5]));
+    assert_eq!(p.norm_squared(), 25.0);
+    assert_eq!(p.dot(&q), -4.0);
+    assert_eq!(Bare(p).norm_squared(), 25.0);
+    assert_eq!(Bare::ones().norm_squared(), 2.0);
+    assert_eq!(Bare::zero().dot(&Bare(q)), 0.0);
+    assert_eq!(Bare(p).dot(&Bare(q)), -4.0);
+    assert!(Bare(p).is_finite());
+    assert!(!Bare(DefaultVector([f64::INFINITY, 0.0])).is_finite());
+    assert!(!Bare(DefaultVector([0.0, f64::NAN])).is_finite());
+    let area = Aabb::new(Bare(p), Bare(q));
+    let cell = Bare::split_area(area, 0b01);
+    assert_eq!((*cell.min().0, *cell.max().0), ([0.5, 0.5], [3.0, 2.25]));
+}
+
+#[test]
+fn test_lerp() {
+    let p = DefaultVector([1.0, -2.0]);
+    let q = DefaultVector([3.0, 2.0]);
+    assert_eq!(*p.lerp(&q, 0.0), *p);
+    assert_eq!(*p.lerp(&q, 1.0), *q);
+    assert_eq!(*p.lerp(&q, 0.25), [1.5, -1.0]);
+}
+
+#[test]
+fn test_min_max() {
+    let p = DefaultVector([1.0, -2.0, 3.0]);
+    let q = DefaultVector([0.0, 5.0, 3.0]);
+    assert_eq!(*p.min(q), [0.0, -2.0, 3.0]);
+    assert_eq!(*p.max(q), [1.0, 5.0, 3.0]);
+}
+
+#[test]
+fn test_components() {
+    let p = DefaultVector([1.0, -2.0, 3.0]);
+    assert_eq!(p.component(1), -2.0);
+    assert_eq!(*p.with_component(2, 0.5), [1.0, -2.0, 0.5]);
+    assert_eq!(*p, [1.0, -2.0, 3.0]);
+}
+
+#[test]
+fn test_tuple_conversions() {
+    let p: DefaultVector<2> = (1.0, 2.0).into();
+    let q = DefaultVector::from((1.0, 2.0, 3.0));
+    assert_eq!(*p, [1.0, 2.0]);
+    assert_eq!(<(f64, f64, f64)>::from(q * 2.0), (2.0, 4.0, 6.0));
+    assert!(p.within(Aabb::new((0.0, 0.0).into(), (2.0, 2.0).into())));
+    assert_eq!(
+        Aabb::from(((2.0, 2.0), (0.0, 0.0))),
+        Aabb::new(DefaultVector([0.0, 0.0]), DefaultVector([2.0, 2.0]))
+    );
+    let cube: Aabb<DefaultVector<3>> = ((0.0, 1.0, 2.0), (3.0, 4.0, 5.0)).into();
+    assert_eq!(cube.max(), q + DefaultVector::ones() * 2.0);
+}
+
+#[test]
+fn test_array_keyed_tree() {
+    use crate::QuadTree;
+    let mut tree = QuadTree::<4, DefaultVector<2>, (), usize>::new(([0.0, 0.0], [4.0, 4.0]));
+    for (i, p) in [[0.5, 0.5], [1.5, 2.5], [3.0, 1.0], [2.0, 2.0], [3.5, 3.5]]
+        .into_iter()
+        .enumerate()
+    {
+        tree.insert(p, (), i).unwrap();
+    }
+    let mut found: Vec<_> = tree
+        .query_area(([1.0, 1.0], [3.0, 3.0]))
+        .into_iter()
+        .map(|(p, _, i)| (<[f64; 2]>::from(*p), *i))
+        .collect();
+    found.sort_by_key(|&(_, i)| i);
+    assert_eq!(found, [([1.5, 2.5], 1), ([3.0, 1.0], 2), ([2.0, 2.0], 3)]);
+    assert_eq!(tree.count_in_area(((0.0, 0.0), (1.0, 1.0))), 1);
+    assert!(tree.contains(&[3.5, 3.5].into()));
 }
 
 #[test]
@@ -51,3 +644,33 @@ fn test_vector_impl() {
     let p = DefaultVector::<2>([1.0, 2.0]);
     assert_eq!(*(p + p), *(p * 2.0));
 }
+
+#[test]
+fn test_arithmetic() {
+    let mut v = DefaultVector::new([1.0, -2.0]);
+    v += DefaultVector::from([0.5, 0.5]);
+    assert_eq!(v, DefaultVector([1.5, -1.5]));
+    v -= DefaultVector::ones();
+    assert_eq!(-v / 2.0, DefaultVector([-0.25, 1.25]));
+    v[1] = 4.0;
+    assert_eq!((v[0], v[1]), (0.5, 4.0));
+    assert_eq!(<[f64; 2]>::from(v), *v.as_array());
+    assert_eq!(DefaultVector::<3>::default(), DefaultVector::zero());
+}
+
+#[test]
+fn test_hash() {
+    use std::collections::HashMap;
+    let mut cells = HashMap::new();
+    cells.insert(DefaultVector([0.0, 1.5]), 'a');
+    cells.insert(DefaultVector([1.5, 0.0]), 'b');
+    assert_eq!(cells.get(&DefaultVector([-0.0, 1.5])), Some(&'a'));
+    assert_eq!(cells.insert(DefaultVector([1.5, -0.0]), 'c'), Some('b'));
+    cells.insert(DefaultVector([f64::NAN, 0.0]), 'd');
+    assert_eq!(cells.get(&DefaultVector([f64::NAN, 0.0])), Some(&'d'));
+    assert_eq!(cells.remove(&DefaultVector([f64::NAN, -0.0])), Some('d'));
+    assert_eq!(cells.get(&DefaultVector([-f64::NAN, 0.0])), None);
+    assert_eq!(cells.len(), 2);
+    let ints: HashMap<_, _> = [(DefaultVector([-1i64, 2]), 'e')].into();
+    assert_eq!(ints[&DefaultVector([-1, 2])], 'e');
+}
@@ -0,0 +1,203 @@
+/*
+[`Vectorial`] for cgmath's `f64` points. cgmath adds vectors to points but
+not points to each other, and a point minus a point is a vector, so
+`Point2` / `Point3` can't meet the trait's `Add` / `Sub` bounds themselves.
+[`CgPoint2`] and [`CgPoint3`] wrap them, adding and subtracting their
+coordinates, and convert to and from the wrapped point.
+*/
+use ::cgmath::{EuclideanSpace, Point2, Point3};
+use core::ops::{Add, Mul, Sub};
+
+use super::{DefaultVector, Vectorial};
+use crate::aabb::Aabb;
+
+fn flat<P, const N: usize>(area: Aabb<P>) -> Aabb<DefaultVector<N>>
+where
+    P: Vectorial + Into<DefaultVector<N>>,
+{
+    Aabb::new(area.min().into(), area.max().into())
+}
+
+macro_rules! cgmath_point {
+    ($($(#[$doc:meta])* $p:ident($point:ident, $n:literal);)*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        #[repr(transparent)]
+        pub struct $p(pub $point<f64>);
+
+        impl Add for $p {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Self(self.0 + other.0.to_vec())
+            }
+        }
+
+        impl Sub for $p {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                Self(self.0 - other.0.to_vec())
+            }
+        }
+
+        impl Mul<f64> for $p {
+            type Output = Self;
+
+            fn mul(self, s: f64) -> Self {
+                Self(self.0 * s)
+            }
+        }
+
+        impl Vectorial for $p {
+            type Scalar = f64;
+            const DIMENSION: Option<usize> = Some($n);
+
+            fn within(&self, area: Aabb<Self>) -> bool {
+                DefaultVector::from(*self).within(flat(area))
+            }
+
+            fn split_area_at(area: Aabb<Self>, at: Self, i: usize) -> Aabb<Self> {
+                let cell = DefaultVector::split_area_at(flat(area), at.into(), i);
+                Aabb::new(cell.min().into(), cell.max().into())
+            }
+
+            fn intersects(a: Aabb<Self>, b: Aabb<Self>) -> bool {
+                DefaultVector::intersects(flat(a), flat(b))
+            }
+
+            fn distance_squared(&self, other: &Self) -> f64 {
+                DefaultVector::from(*self).distance_squared(&(*other).into())
+            }
+
+            fn is_finite(&self) -> bool {
+                DefaultVector::from(*self).is_finite()
+            }
+
+            fn min_distance_squared_to_area(&self, area: Aabb<Self>) -> f64 {
+                DefaultVector::from(*self).min_distance_squared_to_area(flat(area))
+            }
+
+            fn dot(&self, other: &Self) -> f64 {
+                DefaultVector::from(*self).dot(&(*other).into())
+            }
+
+            fn zero() -> Self {
+                DefaultVector::zero().into()
+            }
+
+            fn ones() -> Self {
+                DefaultVector::ones().into()
+            }
+
+            fn min(self, other: Self) -> Self {
+                DefaultVector::from(self).min(other.into()).into()
+            }
+
+            fn max(self, other: Self) -> Self {
+                DefaultVector::from(self).max(other.into()).into()
+            }
+
+            fn component(&self, axis: usize) -> f64 {
+                self.0[axis]
+            }
+
+            fn with_component(&self, axis: usize, val: f64) -> Self {
+                let mut p = *self;
+                p.0[axis] = val;
+                p
+            }
+        }
+
+        impl From<$point<f64>> for $p {
+            fn from(p: $point<f64>) -> Self {
+                Self(p)
+            }
+        }
+
+        impl From<$p> for $point<f64> {
+            fn from(p: $p) -> Self {
+                p.0
+            }
+        }
+
+        impl From<$p> for DefaultVector<$n> {
+            fn from(p: $p) -> Self {
+                Self(p.0.into())
+            }
+        }
+
+        impl From<DefaultVector<$n>> for $p {
+            fn from(v: DefaultVector<$n>) -> Self {
+                Self(v.0.into())
+            }
+        }
+    )*};
+}
+
+cgmath_point! {
+    /// A cgmath `Point2<f64>` stored in a tree, see [`Vectorial`].
+    CgPoint2(Point2, 2);
+    /// A cgmath `Point3<f64>` stored in a tree, see [`Vectorial`].
+    CgPoint3(Point3, 3);
+}
+
+#[test]
+fn test_cgmath_point2() {
+    use crate::QuadTree;
+    let mut tree = QuadTree::<4, CgPoint2, (), usize>::with_bucket_size(
+        (
+            CgPoint2(Point2::new(0.0, 0.0)),
+            CgPoint2(Point2::new(1.0, 1.0)),
+        ),
+        2,
+    );
+    let points: Vec<_> = crate::naive::random_points(200, 19)
+        .into_iter()
+        .map(|p| Point2::new(p[0], p[1]))
+        .collect();
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    tree.root.assert_valid();
+    let (lo, hi) = (Point2::new(0.2, 0.1), Point2::new(0.6, 0.5));
+    let mut found: Vec<_> = tree
+        .query_area((CgPoint2(lo), CgPoint2(hi)))
+        .iter()
+        .map(|(.., i)| **i)
+        .collect();
+    found.sort();
+    let expected: Vec<_> = (0..points.len())
+        .filter(|&i| lo.x <= points[i].x && points[i].x <= hi.x)
+        .filter(|&i| lo.y <= points[i].y && points[i].y <= hi.y)
+        .collect();
+    assert_eq!(found, expected);
+    let (nearest, i) = tree.nearest(CgPoint2(Point2::new(0.5, 0.5))).unwrap();
+    assert_eq!(Point2::from(*nearest), points[*i]);
+}
+
+#[test]
+fn test_cgmath_point3() {
+    use crate::OcTree;
+    let a = CgPoint3(Point3::new(1.0, 2.0, 3.0));
+    let b = CgPoint3(Point3::new(0.5, -1.0, 2.0));
+    assert_eq!((a + b).0, Point3::new(1.5, 1.0, 5.0));
+    assert_eq!((a - b).0, Point3::new(0.5, 3.0, 1.0));
+    assert_eq!((a * 2.0).0, Point3::new(2.0, 4.0, 6.0));
+    assert_eq!(DefaultVector::from(a), DefaultVector([1.0, 2.0, 3.0]));
+    assert_eq!(CgPoint3::from(DefaultVector([1.0, 2.0, 3.0])), a);
+
+    let mut tree: OcTree<CgPoint3, (), char> =
+        OcTree::new((CgPoint3::zero(), CgPoint3::ones() * 4.0));
+    for (p, c) in [
+        ([1.0, 1.0, 1.0], 'a'),
+        ([3.0, 1.0, 2.5], 'b'),
+        ([0.5, 3.5, 3.0], 'c'),
+    ] {
+        tree.insert(Point3::from(p), (), c).unwrap();
+    }
+    let cell = CgPoint3::split_area(tree.bounds(), 0b101);
+    assert_eq!(cell.min().0, Point3::new(2.0, 0.0, 2.0));
+    let (nearest, c) = tree.nearest(CgPoint3(Point3::new(3.0, 0.0, 3.0))).unwrap();
+    assert_eq!((nearest.0, *c), (Point3::new(3.0, 1.0, 2.5), 'b'));
+}
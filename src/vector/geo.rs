@@ -0,0 +1,136 @@
+/*
+[`Vectorial`] for geo-types' `Point<f64>`, axis 0 being `x` (longitude) and
+axis 1 `y` (latitude) as in geo. Points add, subtract and scale like vectors
+there, so they are stored as they are. A `Rect` converts into the query area
+with the same bounds, and a tree hands its points back as a `MultiPoint`.
+*/
+use ::geo_types::{MultiPoint, Point, Rect};
+
+use super::{DefaultVector, Vectorial};
+use crate::QuadTree;
+use crate::aabb::Aabb;
+
+fn flat(area: Aabb<Point<f64>>) -> Aabb<DefaultVector<2>> {
+    Aabb::new(area.min().into(), area.max().into())
+}
+
+impl Vectorial for Point<f64> {
+    type Scalar = f64;
+    const DIMENSION: Option<usize> = Some(2);
+
+    fn within(&self, area: Aabb<Self>) -> bool {
+        DefaultVector::from(*self).within(flat(area))
+    }
+
+    fn split_area_at(area: Aabb<Self>, at: Self, i: usize) -> Aabb<Self> {
+        let cell = DefaultVector::split_area_at(flat(area), at.into(), i);
+        Aabb::new(cell.min().into(), cell.max().into())
+    }
+
+    fn intersects(a: Aabb<Self>, b: Aabb<Self>) -> bool {
+        DefaultVector::intersects(flat(a), flat(b))
+    }
+
+    fn distance_squared(&self, other: &Self) -> f64 {
+        DefaultVector::from(*self).distance_squared(&(*other).into())
+    }
+
+    fn is_finite(&self) -> bool {
+        DefaultVector::from(*self).is_finite()
+    }
+
+    fn min_distance_squared_to_area(&self, area: Aabb<Self>) -> f64 {
+        DefaultVector::from(*self).min_distance_squared_to_area(flat(area))
+    }
+
+    fn dot(&self, other: &Self) -> f64 {
+        DefaultVector::from(*self).dot(&(*other).into())
+    }
+
+    fn zero() -> Self {
+        Point::new(0.0, 0.0)
+    }
+
+    fn ones() -> Self {
+        Point::new(1.0, 1.0)
+    }
+
+    fn min(self, other: Self) -> Self {
+        DefaultVector::from(self).min(other.into()).into()
+    }
+
+    fn max(self, other: Self) -> Self {
+        DefaultVector::from(self).max(other.into()).into()
+    }
+
+    fn component(&self, axis: usize) -> f64 {
+        DefaultVector::from(*self)[axis]
+    }
+
+    fn with_component(&self, axis: usize, val: f64) -> Self {
+        DefaultVector::from(*self).with_component(axis, val).into()
+    }
+}
+
+impl From<Point<f64>> for DefaultVector<2> {
+    fn from(p: Point<f64>) -> Self {
+        Self([p.x(), p.y()])
+    }
+}
+
+impl From<DefaultVector<2>> for Point<f64> {
+    fn from(v: DefaultVector<2>) -> Self {
+        Point::new(v[0], v[1])
+    }
+}
+
+impl From<Rect<f64>> for Aabb<Point<f64>> {
+    fn from(rect: Rect<f64>) -> Self {
+        Aabb::new(rect.min().into(), rect.max().into())
+    }
+}
+
+impl<U, V, S, M> QuadTree<4, Point<f64>, U, V, S, M> {
+    /// Every stored point, in [`QuadTree::iter`]'s order.
+    pub fn to_multipoint(&self) -> MultiPoint<f64> {
+        self.iter().map(|(p, ..)| *p).collect()
+    }
+}
+
+#[test]
+fn test_geo_lon_lat() {
+    use ::geo_types::{coord, point};
+    let cities = [
+        ("Lisbon", -9.14, 38.72),
+        ("Madrid", -3.70, 40.42),
+        ("Paris", 2.35, 48.86),
+        ("Berlin", 13.40, 52.52),
+        ("Rome", 12.50, 41.90),
+        ("Reykjavik", -21.94, 64.15),
+        ("Cairo", 31.24, 30.04),
+    ];
+    let world = Rect::new(coord! { x: -180.0, y: -90.0 }, coord! { x: 180.0, y: 90.0 });
+    let mut tree: QuadTree<4, Point<f64>, (), &str> = QuadTree::with_bucket_size(world, 2);
+    for (name, lon, lat) in cities {
+        tree.insert(point! { x: lon, y: lat }, (), name).unwrap();
+    }
+    tree.root.assert_valid();
+
+    // Roughly western Europe: longitude first, then latitude.
+    let bbox = Rect::new(coord! { x: -10.0, y: 36.0 }, coord! { x: 15.0, y: 55.0 });
+    let mut found: Vec<_> = tree.query_area(bbox).iter().map(|(.., n)| **n).collect();
+    found.sort();
+    assert_eq!(found, ["Berlin", "Lisbon", "Madrid", "Paris", "Rome"]);
+    let (_, nearest) = tree.nearest(point! { x: 2.0, y: 47.0 }).unwrap();
+    assert_eq!(*nearest, "Paris");
+
+    let mut exported: Vec<_> = tree
+        .to_multipoint()
+        .iter()
+        .map(|p| (p.x(), p.y()))
+        .collect();
+    exported.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut expected: Vec<_> = cities.iter().map(|(_, lon, lat)| (*lon, *lat)).collect();
+    expected.sort_by(|a, b| a.0.total_cmp(&b.0));
+    assert_eq!(exported, expected);
+}
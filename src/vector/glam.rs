@@ -0,0 +1,212 @@
+/*
+[`Vectorial`] for glam's vectors, and conversions between each and the
+[`DefaultVector`] of the same length and scalar. Containment, overlap and the corners of
+cells go through glam's own comparisons, `min`, `max` and `select`, which are
+SIMD backed where glam has a backend for the type.
+*/
+use ::glam::{BVec2, BVec3, BVec3A, DVec2, DVec3, Vec2, Vec3, Vec3A};
+
+use super::{DefaultVector, Vectorial, child_halves, square};
+use crate::aabb::Aabb;
+use crate::scalar::Scalar;
+
+/// Implements [`Vectorial`] and the [`DefaultVector`] conversions for glam
+/// vectors of `$n` `$s` components, whose comparisons give `$mask`.
+macro_rules! glam_vectorial {
+    ($($v:ident: [$s:ty; $n:literal], $mask:ident;)*) => {$(
+        impl Vectorial for $v {
+            type Scalar = $s;
+            const DIMENSION: Option<usize> = Some($n);
+
+            fn within(&self, area: Aabb<Self>) -> bool {
+                area.min().cmple(*self).all() && self.cmple(area.max()).all()
+            }
+
+            fn split_area_at(area: Aabb<Self>, at: Self, i: usize) -> Aabb<Self> {
+                let upper = $mask::from(child_halves::<$n>(i));
+                Aabb::new(
+                    $v::select(upper, at, area.min()),
+                    $v::select(upper, area.max(), at),
+                )
+            }
+
+            fn intersects(a: Aabb<Self>, b: Aabb<Self>) -> bool {
+                $v::max(a.min(), b.min()).cmple($v::min(a.max(), b.max())).all()
+            }
+
+            fn distance_squared(&self, other: &Self) -> f64 {
+                let offset = *self - *other;
+                offset.to_array().into_iter().map(|d| square(d.to_f64())).sum()
+            }
+
+            fn is_finite(&self) -> bool {
+                $v::is_finite(*self)
+            }
+
+            fn min_distance_squared_to_area(&self, area: Aabb<Self>) -> f64 {
+                let nearest = $v::min($v::max(*self, area.min()), area.max());
+                Vectorial::distance_squared(self, &nearest)
+            }
+
+            fn dot(&self, other: &Self) -> f64 {
+                let (a, b) = (self.to_array(), other.to_array());
+                (0..$n).map(|k| a[k].to_f64() * b[k].to_f64()).sum()
+            }
+
+            fn zero() -> Self {
+                $v::ZERO
+            }
+
+            fn ones() -> Self {
+                $v::ONE
+            }
+
+            fn min(self, other: Self) -> Self {
+                $v::min(self, other)
+            }
+
+            fn max(self, other: Self) -> Self {
+                $v::max(self, other)
+            }
+
+            fn component(&self, axis: usize) -> $s {
+                self[axis]
+            }
+
+            fn with_component(&self, axis: usize, val: $s) -> Self {
+                let mut v = *self;
+                v[axis] = val;
+                v
+            }
+        }
+
+        impl From<$v> for DefaultVector<$n, $s> {
+            fn from(v: $v) -> Self {
+                Self(v.to_array())
+            }
+        }
+
+        impl From<DefaultVector<$n, $s>> for $v {
+            fn from(v: DefaultVector<$n, $s>) -> Self {
+                $v::from_array(v.0)
+            }
+        }
+    )*};
+}
+
+glam_vectorial! {
+    Vec2: [f32; 2], BVec2;
+    Vec3: [f32; 3], BVec3;
+    Vec3A: [f32; 3], BVec3A;
+    DVec2: [f64; 2], BVec2;
+    DVec3: [f64; 3], BVec3;
+}
+
+#[test]
+fn test_glam_vectorial() {
+    let area = Aabb::new(Vec2::new(0.0, 0.0), Vec2::new(2.0, 4.0));
+    let cell = Vec2::split_area(area, 0b10);
+    assert_eq!(
+        (cell.min(), cell.max()),
+        (Vec2::new(0.0, 2.0), Vec2::new(1.0, 4.0))
+    );
+    assert!(Vec2::new(1.0, 4.0).within(area));
+    assert!(!Vec2::new(1.0, 4.5).within(area));
+    assert!(!Vec2::new(f32::NAN, 1.0).within(area));
+    assert!(!Vectorial::is_finite(&Vec2::new(f32::INFINITY, 1.0)));
+    let other = Aabb::new(Vec2::new(2.0, 1.0), Vec2::new(3.0, 2.0));
+    assert!(Vec2::intersects(area, other));
+    assert!(!Vec2::intersects(
+        area,
+        Aabb::new(Vec2::splat(3.0), Vec2::splat(5.0))
+    ));
+    let p = Vec2::new(3.0, 5.0);
+    assert_eq!(p.min_distance_squared_to_area(area), 2.0);
+    assert_eq!(Vectorial::dot(&p, &Vec2::new(2.0, 1.0)), 11.0);
+
+    let area = Aabb::new(Vec3A::ZERO, Vec3A::splat(2.0));
+    let cells: Vec<_> = (0..8).map(|i| Vec3A::split_area(area, i)).collect();
+    assert_eq!((cells[0].max(), cells[7].min()), (Vec3A::ONE, Vec3A::ONE));
+    assert_eq!(cells[0b101].min(), Vec3A::new(1.0, 0.0, 1.0));
+    assert_eq!(
+        Vec3::new(1.0, 2.0, 3.0).with_component(1, 5.0),
+        Vec3::new(1.0, 5.0, 3.0)
+    );
+}
+
+#[test]
+fn test_glam_trees() {
+    use crate::{OcTree, QuadTree};
+    let mut tree = QuadTree::<4, Vec2, (), usize>::with_bucket_size((Vec2::ZERO, Vec2::ONE), 2);
+    let points: Vec<_> = crate::naive::random_points(300, 83)
+        .into_iter()
+        .map(|p| Vec2::new(p[0] as f32, p[1] as f32))
+        .collect();
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    tree.root.assert_valid();
+    let (lo, hi) = (Vec2::new(0.25, 0.5), Vec2::new(0.75, 0.625));
+    let mut found: Vec<_> = tree
+        .query_area((lo, hi))
+        .iter()
+        .map(|(.., i)| **i)
+        .collect();
+    found.sort();
+    let expected: Vec<_> = (0..points.len())
+        .filter(|&i| lo.cmple(points[i]).all() && points[i].cmple(hi).all())
+        .collect();
+    assert_eq!(found, expected);
+
+    let mut tree: OcTree<Vec3A, (), char> = OcTree::new((Vec3A::ZERO, Vec3A::splat(4.0)));
+    for (p, c) in [
+        ([1.0, 1.0, 1.0], 'a'),
+        ([3.0, 1.0, 2.5], 'b'),
+        ([0.5, 3.5, 3.0], 'c'),
+    ] {
+        tree.insert(Vec3A::from_array(p), (), c).unwrap();
+    }
+    let (nearest, c) = tree.nearest(Vec3A::new(3.0, 0.0, 3.0)).unwrap();
+    assert_eq!((*nearest, *c), (Vec3A::new(3.0, 1.0, 2.5), 'b'));
+}
+
+#[test]
+fn test_glam_f64() {
+    use crate::QuadTree;
+    let area = Aabb::new(DVec3::ZERO, DVec3::new(2.0, 4.0, 8.0));
+    let cell = DVec3::split_area(area, 0b110);
+    assert_eq!(
+        (cell.min(), cell.max()),
+        (DVec3::new(0.0, 2.0, 4.0), DVec3::new(1.0, 4.0, 8.0))
+    );
+    assert!(DVec3::new(2.0, 0.0, 8.0).within(area));
+    assert!(!DVec3::new(2.0, -0.5, 8.0).within(area));
+    assert_eq!(
+        DVec3::new(3.0, 5.0, 8.0).min_distance_squared_to_area(area),
+        2.0
+    );
+
+    // Coordinates glam's `f32` vectors would round.
+    let mut tree: QuadTree<4, DVec2, (), usize> = QuadTree::new((DVec2::ZERO, DVec2::ONE));
+    let (a, b) = (DVec2::new(0.1, 0.3), DVec2::new(0.1 + 1e-12, 0.3));
+    tree.insert(a, (), 0).unwrap();
+    tree.insert(b, (), 1).unwrap();
+    let found = tree.query_area((b, DVec2::ONE));
+    assert_eq!(found.iter().map(|(.., i)| **i).collect::<Vec<_>>(), [1]);
+    assert_eq!(tree.nearest(DVec2::new(0.1, 0.5)).unwrap().1, &0);
+}
+
+#[test]
+fn test_glam_conversions() {
+    let v = DefaultVector([1.0f32, -2.0]);
+    assert_eq!(Vec2::from(v), Vec2::new(1.0, -2.0));
+    assert_eq!(DefaultVector::from(Vec2::from(v)), v);
+    let v = DefaultVector([1.0f32, -2.0, 0.5]);
+    assert_eq!(Vec3::from(v), Vec3::new(1.0, -2.0, 0.5));
+    assert_eq!(DefaultVector::from(Vec3A::from(v)), v);
+    let v = DefaultVector([0.1, 0.2]);
+    assert_eq!(DefaultVector::from(DVec2::from(v)), v);
+    let p: DVec3 = DefaultVector([0.1, 0.2, 0.3]).into();
+    assert_eq!(p, DVec3::new(0.1, 0.2, 0.3));
+    assert_eq!(DefaultVector::from(p), DefaultVector([0.1, 0.2, 0.3]));
+}
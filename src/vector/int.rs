@@ -0,0 +1,255 @@
+use core::ops::{Add, Index, Mul, Sub};
+
+use super::{Vectorial, square};
+use crate::aabb::Aabb;
+
+/// `N`-dimensional point on the integer lattice, for tile and grid worlds.
+/// Unlike a [`DefaultVector`](super::DefaultVector) over `i64`, areas split
+/// into disjoint halves `[lo, mid]` and `[mid + 1, hi]` without ever
+/// rounding, until a cell is a single lattice point. Only distances are
+/// `f64`, taken from differences computed exactly.
+///
+/// An axis of width 0 (a single coordinate) can't be halved, so the lower
+/// and upper cells along it get identical bounds and overlap. Points route
+/// to the upper one: in 2-D, an area one row tall leaves two of its four
+/// children unreachable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct IntVector<const N: usize>(
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))] pub [i64; N],
+);
+
+/// `floor((lo + hi) / 2)` without overflowing.
+fn midpoint(lo: i64, hi: i64) -> i64 {
+    (lo >> 1) + (hi >> 1) + (lo & hi & 1)
+}
+
+/// Exact difference as a float, which `i64` subtraction could overflow.
+fn difference(a: i64, b: i64) -> f64 {
+    (a as i128 - b as i128) as f64
+}
+
+impl<const N: usize> Index<usize> for IntVector<N> {
+    type Output = i64;
+    fn index(&self, axis: usize) -> &i64 {
+        &self.0[axis]
+    }
+}
+
+impl<const N: usize> Add for IntVector<N> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(core::array::from_fn(|k| self[k] + rhs[k]))
+    }
+}
+
+impl<const N: usize> Sub for IntVector<N> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(core::array::from_fn(|k| self[k] - rhs[k]))
+    }
+}
+
+impl<const N: usize> Mul<i64> for IntVector<N> {
+    type Output = Self;
+    fn mul(self, rhs: i64) -> Self::Output {
+        Self(self.0.map(|x| x * rhs))
+    }
+}
+
+impl<const N: usize> From<[i64; N]> for IntVector<N> {
+    fn from(coords: [i64; N]) -> Self {
+        Self(coords)
+    }
+}
+
+impl<const N: usize> Vectorial for IntVector<N> {
+    type Scalar = i64;
+    const DIMENSION: Option<usize> = Some(N);
+
+    fn within(&self, area: Aabb<Self>) -> bool {
+        let (min, max) = (area.min(), area.max());
+        (0..N).all(|k| min[k] <= self[k] && self[k] <= max[k])
+    }
+
+    /// Lower halves end at `floor((lo + hi) / 2)`, so odd widths leave the
+    /// extra lattice point in the lower half.
+    fn split_area(area: Aabb<Self>, i: usize) -> Aabb<Self> {
+        let (lo, hi) = (area.min(), area.max());
+        let at = Self(core::array::from_fn(|k| {
+            if lo[k] == hi[k] {
+                lo[k]
+            } else {
+                midpoint(lo[k], hi[k]) + 1
+            }
+        }));
+        Self::split_area_at(area, at, i)
+    }
+
+    /// `at` is where the upper halves start, the lower ones end just before.
+    /// It is moved into `lo + 1..=hi` so neither half is empty; an axis
+    /// holding a single coordinate is not split at all and both halves
+    /// cover it, routing going to the upper one.
+    fn split_area_at(area: Aabb<Self>, at: Self, i: usize) -> Aabb<Self> {
+        let (mut lo, mut hi) = (area.min(), area.max());
+        for (k, upper) in super::child_halves::<N>(i).into_iter().enumerate() {
+            if lo[k] == hi[k] {
+                continue;
+            }
+            let at = at[k].clamp(lo[k] + 1, hi[k]);
+            if upper {
+                lo.0[k] = at;
+            } else {
+                hi.0[k] = at - 1;
+            }
+        }
+        Aabb::new(lo, hi)
+    }
+
+    fn intersects(a: Aabb<Self>, b: Aabb<Self>) -> bool {
+        (0..N).all(|k| a.min()[k].max(b.min()[k]) <= a.max()[k].min(b.max()[k]))
+    }
+
+    fn distance_squared(&self, other: &Self) -> f64 {
+        (0..N).map(|k| square(difference(self[k], other[k]))).sum()
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    fn min_distance_squared_to_area(&self, area: Aabb<Self>) -> f64 {
+        (0..N)
+            .map(|k| {
+                let nearest = self[k].clamp(area.min()[k], area.max()[k]);
+                square(difference(self[k], nearest))
+            })
+            .sum()
+    }
+
+    /// Moves each component by its distance to `other` scaled by `t`,
+    /// rounded half upwards.
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        Self(core::array::from_fn(|k| {
+            self[k] + <i64 as crate::Scalar>::from_f64(difference(other[k], self[k]) * t)
+        }))
+    }
+
+    fn dot(&self, other: &Self) -> f64 {
+        (0..N)
+            .map(|k| (self[k] as i128 * other[k] as i128) as f64)
+            .sum()
+    }
+
+    fn zero() -> Self {
+        Self([0; N])
+    }
+
+    fn ones() -> Self {
+        Self([1; N])
+    }
+
+    fn min(self, other: Self) -> Self {
+        Self(core::array::from_fn(|k| self[k].min(other[k])))
+    }
+
+    fn max(self, other: Self) -> Self {
+        Self(core::array::from_fn(|k| self[k].max(other[k])))
+    }
+
+    fn component(&self, axis: usize) -> i64 {
+        self[axis]
+    }
+
+    fn with_component(&self, axis: usize, val: i64) -> Self {
+        let mut v = *self;
+        v.0[axis] = val;
+        v
+    }
+}
+
+#[test]
+fn test_int_split_area() {
+    let split = |lo: [i64; 2], hi: [i64; 2]| {
+        let area = Aabb::new(IntVector(lo), IntVector(hi));
+        [0, 1, 2, 3].map(|i| {
+            let cell = area.split(i);
+            (cell.min().0, cell.max().0)
+        })
+    };
+    assert_eq!(
+        split([-8, -8], [7, 7]),
+        [
+            ([-8, -8], [-1, -1]),
+            ([0, -8], [7, -1]),
+            ([-8, 0], [-1, 7]),
+            ([0, 0], [7, 7]),
+        ]
+    );
+    // Odd widths keep the extra coordinate below, single ones aren't split.
+    assert_eq!(
+        split([-3, 5], [-1, 5]),
+        [
+            ([-3, 5], [-2, 5]),
+            ([-1, 5], [-1, 5]),
+            ([-3, 5], [-2, 5]),
+            ([-1, 5], [-1, 5]),
+        ]
+    );
+    assert_eq!(midpoint(i64::MIN, i64::MAX), -1);
+    // A single coordinate at the top of the range has no `mid + 1`.
+    assert_eq!(
+        split([0, i64::MAX], [3, i64::MAX]),
+        [
+            ([0, i64::MAX], [1, i64::MAX]),
+            ([2, i64::MAX], [3, i64::MAX]),
+            ([0, i64::MAX], [1, i64::MAX]),
+            ([2, i64::MAX], [3, i64::MAX]),
+        ]
+    );
+    let area = Aabb::new(IntVector([0, 0]), IntVector([4, 4]));
+    assert_eq!(
+        IntVector([0, 0]).lerp(&IntVector([4, 3]), 0.5),
+        IntVector([2, 2])
+    );
+    assert_eq!(
+        Vectorial::split_area_at(area, IntVector([0, 9]), 3).min(),
+        IntVector([1, 4])
+    );
+}
+
+#[test]
+fn test_int_tree() {
+    use crate::QuadTree;
+    let bounds = (IntVector([-8, -8]), IntVector([7, 7]));
+    let mut tree = QuadTree::<4, _, (), usize>::with_bucket_size(bounds, 1);
+    // Every split plane is crossed by the lattice points on either side.
+    let boundaries = [-8, -5, -4, -3, -2, -1, 0, 1, 2, 3, 4, 7];
+    for x in boundaries {
+        for y in boundaries {
+            tree.insert(IntVector([x, y]), (), 0).unwrap();
+        }
+    }
+    tree.insert(IntVector([7, 7]), (), 1).unwrap();
+    tree.root.assert_valid();
+    assert_eq!(tree.len(), 145);
+    assert_eq!(tree.depth(), 5);
+    for (_, area, position) in tree.root.iter_levels() {
+        if let Some(p) = position {
+            assert!(p.within(*area));
+            // Leaves only hold several points at the same position.
+            assert!(tree.query_area(*area).iter().all(|(q, ..)| *q == p));
+        }
+    }
+    let found = tree.query_area((IntVector([-1, -1]), IntVector([0, 0])));
+    assert_eq!(found.len(), 4);
+    // Both points at the corner share a bucket instead of splitting on.
+    let corner = (IntVector([7, 7]), IntVector([7, 7]));
+    assert_eq!(tree.count_in_area(corner), 2);
+    assert_eq!(
+        tree.nearest(IntVector([6, 6])).map(|(p, _)| *p),
+        Some(IntVector([7, 7]))
+    );
+    assert_eq!(tree.within_radius(IntVector([-1, 0]), 1.0).len(), 5);
+}
@@ -0,0 +1,83 @@
+/*
+Conversions between [`DefaultVector`] and mint's `f64` points and vectors, so
+tree positions reach any crate that speaks mint. Trees take `impl Into<T>`
+on insertion, so mint values go straight in.
+*/
+use ::mint::{Point2, Point3, Vector2, Vector3};
+
+use super::DefaultVector;
+
+macro_rules! mint_conversions {
+    ($($mint:ident, $n:literal;)*) => {$(
+        impl From<$mint<f64>> for DefaultVector<$n> {
+            fn from(v: $mint<f64>) -> Self {
+                Self(v.into())
+            }
+        }
+
+        impl From<DefaultVector<$n>> for $mint<f64> {
+            fn from(v: DefaultVector<$n>) -> Self {
+                v.0.into()
+            }
+        }
+    )*};
+}
+
+mint_conversions! {
+    Point2, 2;
+    Point3, 3;
+    Vector2, 2;
+    Vector3, 3;
+}
+
+#[test]
+fn test_mint_round_trips() {
+    let v = DefaultVector([1.5, -2.0]);
+    let p: Point2<f64> = v.into();
+    assert_eq!((p.x, p.y), (1.5, -2.0));
+    assert_eq!(DefaultVector::from(p), v);
+    assert_eq!(DefaultVector::from(Vector2::from(v)), v);
+    let v = DefaultVector([0.1, 0.2, 0.3]);
+    let p: Point3<f64> = v.into();
+    assert_eq!((p.x, p.y, p.z), (0.1, 0.2, 0.3));
+    assert_eq!(DefaultVector::from(p), v);
+    assert_eq!(DefaultVector::from(Vector3::from(v)), v);
+}
+
+#[test]
+fn test_mint_tree() {
+    use crate::{OcTree, QuadTree};
+    let mut tree: QuadTree<4, DefaultVector<2>, (), usize> =
+        QuadTree::new((DefaultVector([0.0, 0.0]), DefaultVector([1.0, 1.0])));
+    let points: Vec<_> = crate::naive::random_points(100, 29)
+        .into_iter()
+        .map(Point2::from)
+        .collect();
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(*p, (), i).unwrap();
+    }
+    let lo = Point2 { x: 0.3, y: 0.2 };
+    let hi = Point2 { x: 0.8, y: 0.6 };
+    let mut found: Vec<_> = tree
+        .query_area((DefaultVector::from(lo), DefaultVector::from(hi)))
+        .iter()
+        .map(|(.., i)| **i)
+        .collect();
+    found.sort();
+    let expected: Vec<_> = (0..points.len())
+        .filter(|&i| lo.x <= points[i].x && points[i].x <= hi.x)
+        .filter(|&i| lo.y <= points[i].y && points[i].y <= hi.y)
+        .collect();
+    assert_eq!(found, expected);
+    let (nearest, i) = tree.nearest(Point2 { x: 0.5, y: 0.5 }.into()).unwrap();
+    assert_eq!(Point2::from(*nearest), points[*i]);
+
+    let mut tree: OcTree<DefaultVector<3>, (), char> =
+        OcTree::new((DefaultVector([0.0; 3]), DefaultVector([4.0; 3])));
+    tree.insert(Vector3::from([1.0, 3.0, 2.0]), (), 'a')
+        .unwrap();
+    tree.insert(Point3::from([3.0, 1.0, 2.0]), (), 'b').unwrap();
+    let (lo, hi) = (Point3::from([2.0, 0.0, 0.0]), Point3::from([4.0; 3]));
+    let found = tree.query_area((DefaultVector::from(lo), DefaultVector::from(hi)));
+    assert_eq!(found.iter().map(|(.., c)| **c).collect::<Vec<_>>(), ['b']);
+}
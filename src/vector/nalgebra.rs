@@ -0,0 +1,188 @@
+/*
+[`Vectorial`] for nalgebra's fixed size column vectors, and conversions
+between [`DefaultVector`] and both those and nalgebra's points.
+
+`Point<S, N>` itself can't be a [`Vectorial`]: nalgebra adds vectors to
+points but not points to each other, and a point minus a point is a vector,
+so it has neither `Add<Output = Self>` nor `Sub<Output = Self>`. Trees store
+the point's `coords` instead, `Point::from` turns them back into one.
+*/
+use ::nalgebra::{ClosedAddAssign, ClosedMulAssign, ClosedSubAssign, Point, SVector};
+
+use super::{DefaultVector, Vectorial, child_halves, square};
+use crate::aabb::Aabb;
+use crate::scalar::Scalar;
+
+impl<S, const N: usize> Vectorial for SVector<S, N>
+where
+    S: Scalar + ::nalgebra::Scalar + ClosedAddAssign + ClosedSubAssign + ClosedMulAssign,
+{
+    type Scalar = S;
+    const DIMENSION: Option<usize> = Some(N);
+
+    fn within(&self, area: Aabb<Self>) -> bool {
+        let (min, max) = (area.min(), area.max());
+        (0..N).all(|k| min[k] <= self[k] && self[k] <= max[k])
+    }
+
+    fn split_area_at(area: Aabb<Self>, at: Self, i: usize) -> Aabb<Self> {
+        let (mut lo, mut hi) = (area.min(), area.max());
+        for (k, upper) in child_halves::<N>(i).into_iter().enumerate() {
+            if upper {
+                lo[k] = at[k];
+            } else {
+                hi[k] = at[k];
+            }
+        }
+        Aabb::new(lo, hi)
+    }
+
+    fn intersects(a: Aabb<Self>, b: Aabb<Self>) -> bool {
+        (0..N).all(|k| a.min()[k].max(b.min()[k]) <= a.max()[k].min(b.max()[k]))
+    }
+
+    fn distance_squared(&self, other: &Self) -> f64 {
+        (0..N).map(|k| square((self[k] - other[k]).to_f64())).sum()
+    }
+
+    fn is_finite(&self) -> bool {
+        self.iter().all(|x| x.is_finite())
+    }
+
+    fn min_distance_squared_to_area(&self, area: Aabb<Self>) -> f64 {
+        (0..N)
+            .map(|k| {
+                let nearest = self[k].max(area.min()[k]).min(area.max()[k]);
+                square((self[k] - nearest).to_f64())
+            })
+            .sum()
+    }
+
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        Self::from_fn(|k, _| S::from_f64(self[k].to_f64() * (1.0 - t) + other[k].to_f64() * t))
+    }
+
+    fn dot(&self, other: &Self) -> f64 {
+        (0..N).map(|k| self[k].to_f64() * other[k].to_f64()).sum()
+    }
+
+    fn zero() -> Self {
+        Self::from_element(S::ZERO)
+    }
+
+    fn ones() -> Self {
+        Self::from_element(S::ONE)
+    }
+
+    fn min(self, other: Self) -> Self {
+        Self::from_fn(|k, _| self[k].min(other[k]))
+    }
+
+    fn max(self, other: Self) -> Self {
+        Self::from_fn(|k, _| self[k].max(other[k]))
+    }
+
+    fn component(&self, axis: usize) -> S {
+        self[axis]
+    }
+
+    fn with_component(&self, axis: usize, val: S) -> Self {
+        let mut v = *self;
+        v[axis] = val;
+        v
+    }
+}
+
+impl<S: ::nalgebra::Scalar, const N: usize> From<SVector<S, N>> for DefaultVector<N, S> {
+    fn from(v: SVector<S, N>) -> Self {
+        Self(v.into())
+    }
+}
+
+impl<S: ::nalgebra::Scalar, const N: usize> From<DefaultVector<N, S>> for SVector<S, N> {
+    fn from(v: DefaultVector<N, S>) -> Self {
+        v.0.into()
+    }
+}
+
+impl<S: ::nalgebra::Scalar, const N: usize> From<Point<S, N>> for DefaultVector<N, S> {
+    fn from(p: Point<S, N>) -> Self {
+        p.coords.into()
+    }
+}
+
+impl<S: ::nalgebra::Scalar, const N: usize> From<DefaultVector<N, S>> for Point<S, N> {
+    fn from(v: DefaultVector<N, S>) -> Self {
+        v.0.into()
+    }
+}
+
+#[test]
+fn test_nalgebra_point2() {
+    use crate::QuadTree;
+    use ::nalgebra::{Point2, Vector2};
+    let mut tree = QuadTree::<4, Vector2<f64>, (), usize>::with_bucket_size(
+        (Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0)),
+        2,
+    );
+    let points: Vec<_> = crate::naive::random_points(200, 7)
+        .into_iter()
+        .map(|p| Point2::new(p[0], p[1]))
+        .collect();
+    for (i, p) in points.iter().enumerate() {
+        tree.insert(p.coords, (), i).unwrap();
+    }
+    tree.root.assert_valid();
+    let (lo, hi) = (Point2::new(0.2, 0.1), Point2::new(0.6, 0.5));
+    let mut found: Vec<_> = tree
+        .query_area((lo.coords, hi.coords))
+        .iter()
+        .map(|(.., i)| **i)
+        .collect();
+    found.sort();
+    let expected: Vec<_> = (0..points.len())
+        .filter(|&i| (0..2).all(|k| lo[k] <= points[i][k] && points[i][k] <= hi[k]))
+        .collect();
+    assert_eq!(found, expected);
+    let target = Point2::new(0.5, 0.5);
+    let (nearest, i) = tree.nearest(target.coords).unwrap();
+    assert_eq!(Point2::from(*nearest), points[*i]);
+    let best = nearest.distance_squared(&target.coords);
+    assert!(
+        points
+            .iter()
+            .all(|p| p.coords.distance_squared(&target.coords) >= best)
+    );
+}
+
+#[test]
+fn test_nalgebra_point3() {
+    use crate::OcTree;
+    use ::nalgebra::{Point3, Vector3};
+    let mut tree: OcTree<Vector3<f64>, (), char> =
+        OcTree::new((Vector3::zeros(), Vector3::repeat(4.0)));
+    for (p, c) in [
+        ([1.0, 1.0, 1.0], 'a'),
+        ([3.0, 1.0, 2.5], 'b'),
+        ([0.5, 3.5, 3.0], 'c'),
+    ] {
+        tree.insert(Point3::from(p).coords, (), c).unwrap();
+    }
+    let found = tree.query_area((Vector3::repeat(0.0), Vector3::new(4.0, 2.0, 4.0)));
+    let mut found: Vec<_> = found.iter().map(|(.., c)| **c).collect();
+    found.sort();
+    assert_eq!(found, ['a', 'b']);
+}
+
+#[test]
+fn test_nalgebra_conversions() {
+    use ::nalgebra::{Point2, Point3, Vector3};
+    let v = DefaultVector([1.0, -2.0, 0.5]);
+    let p: Point3<f64> = v.into();
+    assert_eq!(p, Point3::new(1.0, -2.0, 0.5));
+    assert_eq!(DefaultVector::from(p), v);
+    assert_eq!(Vector3::from(v), p.coords);
+    assert_eq!(DefaultVector::from(p.coords), v);
+    let q: DefaultVector<2, f32> = Point2::new(3.0f32, 4.0).into();
+    assert_eq!(q, DefaultVector([3.0, 4.0]));
+}
@@ -0,0 +1,62 @@
+/*
+JavaScript bindings. Exported types can't be generic, so [`WasmQuadTree2D`]
+fixes a plane of `f64` coordinates holding arbitrary JS values, and its
+methods take plain numbers.
+*/
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use crate::quadtree2d::QuadTree2D;
+use crate::vector::DefaultVector;
+
+/// [`QuadTree2D`] of JS values for JavaScript callers.
+#[wasm_bindgen]
+pub struct WasmQuadTree2D {
+    tree: QuadTree2D<DefaultVector<2>, (), JsValue>,
+}
+
+#[wasm_bindgen]
+impl WasmQuadTree2D {
+    /// Empty tree covering `[min_x, max_x] × [min_y, max_y]`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> WasmQuadTree2D {
+        let bounds = (DefaultVector([min_x, min_y]), DefaultVector([max_x, max_y]));
+        WasmQuadTree2D {
+            tree: QuadTree2D::new(bounds),
+        }
+    }
+
+    /// Stores `data` at `(x, y)`, throwing the
+    /// [`InsertError`](crate::InsertError) message when it can't.
+    pub fn insert(&mut self, x: f64, y: f64, data: JsValue) -> Result<(), JsError> {
+        self.tree
+            .insert(DefaultVector([x, y]), (), data)
+            .map(|_| ())
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Data of every point within the box, boundaries included.
+    pub fn query_range(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Array {
+        let area = (DefaultVector([min_x, min_y]), DefaultVector([max_x, max_y]));
+        self.tree
+            .query_area(area)
+            .into_iter()
+            .map(|(.., data)| data.clone())
+            .collect()
+    }
+
+    /// Data of the point closest to `(x, y)`, `undefined` when empty.
+    pub fn nearest(&self, x: f64, y: f64) -> JsValue {
+        self.tree
+            .nearest(DefaultVector([x, y]))
+            .map_or(JsValue::UNDEFINED, |(_, data)| data.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+}
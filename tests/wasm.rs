@@ -0,0 +1,56 @@
+//! Run with `wasm-pack test --node --features wasm`.
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use js_sys::Array;
+use quadrs::WasmQuadTree2D;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn names(found: Array) -> Vec<String> {
+    let mut names: Vec<_> = found.iter().map(|v| v.as_string().unwrap()).collect();
+    names.sort();
+    names
+}
+
+#[wasm_bindgen_test]
+fn test_insert_and_query_range() {
+    let mut tree = WasmQuadTree2D::new(0.0, 0.0, 10.0, 10.0);
+    assert!(tree.is_empty());
+    for (x, y, name) in [
+        (1.0, 1.0, "a"),
+        (2.0, 8.0, "b"),
+        (7.5, 3.0, "c"),
+        (9.0, 9.0, "d"),
+    ] {
+        tree.insert(x, y, JsValue::from_str(name)).unwrap();
+    }
+    assert_eq!(tree.len(), 4);
+    assert_eq!(names(tree.query_range(0.0, 0.0, 8.0, 5.0)), ["a", "c"]);
+    assert_eq!(
+        names(tree.query_range(2.0, 0.0, 10.0, 10.0)),
+        ["b", "c", "d"]
+    );
+    assert_eq!(tree.query_range(3.0, 4.0, 6.0, 6.0).length(), 0);
+
+    // Points at the same position are all kept.
+    tree.insert(1.0, 1.0, JsValue::from_str("e")).unwrap();
+    assert_eq!(names(tree.query_range(0.0, 0.0, 1.0, 1.0)), ["a", "e"]);
+}
+
+#[wasm_bindgen_test]
+fn test_insert_errors() {
+    let mut tree = WasmQuadTree2D::new(0.0, 0.0, 1.0, 1.0);
+    assert!(tree.insert(2.0, 0.5, JsValue::NULL).is_err());
+    assert!(tree.insert(f64::NAN, 0.5, JsValue::NULL).is_err());
+    assert!(tree.is_empty());
+}
+
+#[wasm_bindgen_test]
+fn test_nearest() {
+    let mut tree = WasmQuadTree2D::new(-1.0, -1.0, 1.0, 1.0);
+    assert!(tree.nearest(0.0, 0.0).is_undefined());
+    tree.insert(-0.5, 0.5, JsValue::from_f64(1.0)).unwrap();
+    tree.insert(0.6, -0.2, JsValue::from_f64(2.0)).unwrap();
+    assert_eq!(tree.nearest(0.5, 0.0).as_f64(), Some(2.0));
+    assert_eq!(tree.nearest(-1.0, 1.0).as_f64(), Some(1.0));
+}